@@ -0,0 +1,5 @@
+//! Builds the C API of hpart, declared in `include/hpart.h`, as a shared
+//! library. The functions themselves live in hpart behind its `capi` feature;
+//! linking the crate is enough to export them.
+
+pub use hpart;
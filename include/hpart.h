@@ -0,0 +1,123 @@
+/* C API of the hpart hypergraph partitioner. Link against the shared library
+ * libhpart_capi, built by `cargo build --release -p hpart-capi`.
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/hpart.h`. */
+
+#ifndef HPART_H
+#define HPART_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of a call to the C API.
+ */
+typedef enum HpartStatus {
+  HPART_STATUS_OK = 0,
+  /**
+   * A pointer was null, `k` was zero, or the CSR arrays were inconsistent.
+   */
+  HPART_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * An internal error. Please report it.
+   */
+  HPART_STATUS_PANIC = 2,
+} HpartStatus;
+
+/**
+ * A hypergraph owned by the library.
+ */
+typedef struct HpartHypergraph HpartHypergraph;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a hypergraph from hMETIS-style CSR arrays: the pins of net `e` are
+ * `eind[eptr[e]..eptr[e + 1]]`. `capacities` and `weights` may be null for
+ * unit capacities and weights. The arrays are copied. On success, stores
+ * the hypergraph in `*out`, to be freed with `hpart_hypergraph_free`.
+ *
+ * # Safety
+ *
+ * `eptr` must hold `num_nets + 1` elements, `eind` `eptr[num_nets]`,
+ * `capacities` `num_vertices` and `weights` `num_nets`. `out` must be valid
+ * for writes.
+ */
+HpartStatus hpart_hypergraph_new(uintptr_t num_vertices,
+                                 uintptr_t num_nets,
+                                 const uintptr_t *eptr,
+                                 const uint32_t *eind,
+                                 const float *capacities,
+                                 const float *weights,
+                                 HpartHypergraph **out);
+
+/**
+ * Frees a hypergraph created by `hpart_hypergraph_new`. Does nothing if `h`
+ * is null.
+ *
+ * # Safety
+ *
+ * `h` must be null or come from `hpart_hypergraph_new`, and must not be used
+ * afterwards.
+ */
+void hpart_hypergraph_free(HpartHypergraph *h);
+
+/**
+ * Partitions `h` into `k` blocks with the default configuration, allowing
+ * each block `(1 + epsilon) / k` of the total capacity. Runs with the same
+ * seed give the same result. Stores the block of every vertex in `blocks`,
+ * and the cut and connectivity metric in `*cut` and `*km1` unless they are
+ * null.
+ *
+ * # Safety
+ *
+ * `h` must come from `hpart_hypergraph_new`. `blocks` must be valid for
+ * writing one element per vertex, and `cut` and `km1` for one element each
+ * unless they are null.
+ */
+HpartStatus hpart_partition(const HpartHypergraph *h,
+                            uintptr_t k,
+                            float epsilon,
+                            uint64_t seed,
+                            uint32_t *blocks,
+                            float *cut,
+                            float *km1);
+
+/**
+ * Drop-in replacement for `HMETIS_PartRecursive` of hMETIS 1.5. Every
+ * bisection leaves each side between `50 - ubfactor` and `50 + ubfactor`
+ * percent of the capacity. If `options[0]` is nonzero, `options[1]` is the
+ * number of tries and `options[7]` the seed, or -1 for a random one; the
+ * other options are ignored. `vwgts` and `hewgts` may be null for unit
+ * weights. Stores the block of every vertex in `part` and the total weight
+ * of the cut nets in `*edgecut`, or -1 in `*edgecut` if the arguments are
+ * invalid.
+ *
+ * # Safety
+ *
+ * The arrays must be as documented by hMETIS: `eptr` with `nhedges + 1`
+ * elements, `eind` with `eptr[nhedges]`, `vwgts` and `part` with `nvtxs`,
+ * `hewgts` with `nhedges`, and `options` with 9. `options` may be null for
+ * the defaults.
+ */
+void HMETIS_PartRecursive(int nvtxs,
+                          int nhedges,
+                          const int *vwgts,
+                          const int *eptr,
+                          const int *eind,
+                          const int *hewgts,
+                          int nparts,
+                          int ubfactor,
+                          const int *options,
+                          int *part,
+                          int *edgecut);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HPART_H */
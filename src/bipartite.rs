@@ -1,11 +1,16 @@
 use std::iter::zip;
 
 use bitvec::prelude::*;
+use rayon::prelude::*;
+
+use crate::preprocess::remove_redundant_nets;
 
 pub type Index = u32;
 
 /// The data structure internal to this hypergraph partitioner is a bipartite
-/// graph between pins and nets.
+/// graph between pins and nets. Coarsening disables vertices and the nets it
+/// makes redundant, so vertex IDs range over `0..pin_index_space_size()`, but
+/// only `pins()` are live, and likewise for nets.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Bipartite {
     // V, E, and A arrays as described in section 4.5 of Schlag '2015.
@@ -17,15 +22,20 @@ pub(crate) struct Bipartite {
     // disabled.
     v_enabled: BitVec,
     num_disabled: usize,
+    e_enabled: BitVec,
 
     // Capacities and weights of pins and nets.
     c: Vec<f32>,
     w: Vec<f32>,
+
+    // Whether every net has at most two pins. Contraction never grows nets,
+    // so this holds for the whole hierarchy.
+    graph: bool,
 }
 
 /// Contractions produce mementos that can be applied in reverse to perform
 /// uncontractions.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub(crate) struct Memento {
     // Stores the pair of vertices contracted, as well as the slice into A of u
     // prior to contraction.
@@ -33,41 +43,166 @@ pub(crate) struct Memento {
     pub(crate) v: Index,
     u_idx: Index,
     u_len: Index,
+    // The nets removed after the contraction, in order.
+    removed: Vec<RemovedNet>,
+}
+
+/// A net removed by a contraction, and the net its weight went to, if any,
+/// along with that net's weight before.
+#[derive(Clone, Copy)]
+struct RemovedNet {
+    e: Index,
+    representative: Option<Index>,
+    weight: f32,
 }
 
 impl Bipartite {
     pub(crate) fn new(capacities: &[f32], weights: &[f32], nets: &[&[Index]]) -> Self {
+        let mut bipartite = Bipartite::with_nets(capacities, weights, nets);
+        let num_v = capacities.len();
+
+        // Transpose the nets by counting sort. Pins repeated within a net are
+        // only counted once, which `last` keeps track of.
+        let mut last = vec![Index::MAX; num_v];
+        let mut degrees = vec![0; num_v];
+        for (e, net) in nets.iter().enumerate() {
+            for v in *net {
+                if last[*v as usize] != e as Index {
+                    last[*v as usize] = e as Index;
+                    degrees[*v as usize] += 1;
+                }
+            }
+        }
+        let mut next = bipartite.a.len();
+        for degree in degrees {
+            bipartite.v.push((next as Index, degree));
+            next += degree as usize;
+        }
+        bipartite.a.resize(next, 0);
+        let mut filled = vec![0; num_v];
+        last.fill(Index::MAX);
+        for (e, net) in nets.iter().enumerate() {
+            for v in *net {
+                let v = *v as usize;
+                if last[v] != e as Index {
+                    last[v] = e as Index;
+                    bipartite.a[(bipartite.v[v].0 + filled[v]) as usize] = e as Index;
+                    filled[v] += 1;
+                }
+            }
+        }
+        bipartite
+    }
+
+    /// Like `new`, but takes the nets of every vertex from the caller instead
+    /// of computing them. The nets of vertex `v` are
+    /// `vind[vptr[v]..vptr[v + 1]]`, in ascending order. Checking them against
+    /// the nets takes a single pass over the pins, which is still cheaper than
+    /// transposing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vptr` doesn't hold one more entry than there are vertices,
+    /// or if the nets of the vertices aren't exactly the transpose of the pins
+    /// of the nets, e.g. because a net lists a pin twice.
+    pub(crate) fn with_incidence(
+        capacities: &[f32],
+        weights: &[f32],
+        nets: &[&[Index]],
+        vptr: &[usize],
+        vind: &[Index],
+    ) -> Self {
+        assert_eq!(
+            vptr.len(),
+            capacities.len() + 1,
+            "one more vptr than vertices"
+        );
+        assert_eq!(
+            vptr.last(),
+            Some(&vind.len()),
+            "vptr must end at vind's length"
+        );
+        assert!(vptr.windows(2).all(|w| w[0] <= w[1]), "vptr must ascend");
+        let mut bipartite = Bipartite::with_nets(capacities, weights, nets);
+        let offset = bipartite.a.len();
+        assert_eq!(vind.len(), offset, "vind must list every pin once");
+
+        // Visiting the nets in order, every pin must be the next net of its
+        // vertex. With as many entries in `vind` as there are pins, that fills
+        // every list exactly.
+        let mut filled = vec![0; capacities.len()];
+        for (e, net) in nets.iter().enumerate() {
+            for v in *net {
+                let v = *v as usize;
+                let idx = vptr[v] + filled[v];
+                assert!(
+                    idx < vptr[v + 1]
+                        && vind[idx] == e as Index
+                        && (filled[v] == 0 || vind[idx - 1] != e as Index),
+                    "vind doesn't match the nets at vertex {v}"
+                );
+                filled[v] += 1;
+            }
+        }
+        for v in vptr.windows(2) {
+            bipartite
+                .v
+                .push(((offset + v[0]) as Index, (v[1] - v[0]) as Index));
+        }
+        bipartite.a.extend_from_slice(vind);
+        bipartite
+    }
+
+    /// A hypergraph with the given nets, but no nets listed for its vertices
+    /// yet.
+    fn with_nets(capacities: &[f32], weights: &[f32], nets: &[&[Index]]) -> Self {
         let num_v = capacities.len();
         let num_e = weights.len();
         assert_eq!(num_e, nets.len());
+        let num_pins: usize = nets.iter().map(|net| net.len()).sum();
         let mut bipartite = Bipartite {
-            v: vec![],
-            e: vec![],
-            a: vec![],
+            v: Vec::with_capacity(num_v),
+            e: Vec::with_capacity(num_e),
+            a: Vec::with_capacity(2 * num_pins),
             v_enabled: bitvec![usize, Lsb0; 1; num_v],
             num_disabled: 0,
+            e_enabled: bitvec![usize, Lsb0; 1; num_e],
             c: Vec::from(capacities),
             w: Vec::from(weights),
+            graph: nets.iter().all(|net| net.len() <= 2),
         };
-
         for net in nets {
             bipartite
                 .e
                 .push((bipartite.a.len() as Index, net.len() as Index));
             bipartite.a.extend(*net);
         }
-        for v_idx in 0..num_v {
-            let old_a = bipartite.a.len();
-            for (e_idx, net) in nets.into_iter().enumerate() {
-                if net.into_iter().any(|p| *p == v_idx as Index) {
-                    bipartite.a.push(e_idx as Index);
-                }
-            }
-            let num_nets = bipartite.a.len() - old_a;
-            bipartite.v.push((old_a as Index, num_nets as Index));
+        bipartite
+    }
+
+    /// Builds the hypergraph obtained by contracting every cluster into a single
+    /// vertex. `clusters` maps each enabled vertex to a dense cluster ID. Nets
+    /// left with a single pin are dropped, and nets that became identical are
+    /// merged.
+    pub(crate) fn contract_clusters(&self, clusters: &[Index], num_clusters: usize) -> Bipartite {
+        let mut capacities = vec![0.0; num_clusters];
+        for v in self.pins() {
+            capacities[clusters[v as usize] as usize] += self.c[v as usize];
         }
 
-        bipartite
+        let nets: Vec<_> = self.nets().collect();
+        let (weights, nets): (Vec<_>, Vec<_>) = nets
+            .into_par_iter()
+            .map(|e| {
+                let mut net: Vec<_> = self.pins_in_net(e).map(|v| clusters[v as usize]).collect();
+                net.sort_unstable();
+                net.dedup();
+                (self.w[e as usize], net)
+            })
+            .unzip();
+        let (weights, nets) = remove_redundant_nets(weights, nets);
+        let nets: Vec<&[_]> = nets.iter().map(|net| &**net).collect();
+        Bipartite::new(&capacities, &weights, &nets)
     }
 
     /// Implements Algorithm 2: Contract from Schlag '2015.
@@ -75,8 +210,6 @@ impl Bipartite {
         assert_ne!(u, v);
         let u_idx = self.v[u as usize].0;
         let u_len = self.v[u as usize].1;
-        let memento = Memento { u, v, u_idx, u_len };
-
         let c_v = self.c[v as usize];
         self.c[u as usize] += c_v;
         let mut copy = true;
@@ -88,9 +221,7 @@ impl Bipartite {
             let mut tau = l;
             for i in e_idx..=l {
                 if self.a[i as usize] == v {
-                    let a_i = self.a[i as usize];
-                    self.a[i as usize] = self.a[l as usize];
-                    self.a[l as usize] = a_i;
+                    self.a.swap(i as usize, l as usize);
                 }
 
                 if self.a[i as usize] == u {
@@ -115,11 +246,118 @@ impl Bipartite {
 
         self.v_enabled.set(v as usize, false);
         self.num_disabled += 1;
-        memento
+        let removed = self.remove_redundant_nets(u, v);
+        Memento {
+            u,
+            v,
+            u_idx,
+            u_len,
+            removed,
+        }
+    }
+
+    /// Removes the nets that contracting `v` into `u` made redundant, as
+    /// `contract_clusters` does for a whole level: nets left with `u` as their
+    /// only pin are dropped, and nets with the same pins as another net of `u`
+    /// are merged into it. Only the nets of `v` changed, so only they can have
+    /// become single-pin, and every new pair of identical nets contains `u`.
+    fn remove_redundant_nets(&mut self, u: Index, v: Index) -> Vec<RemovedNet> {
+        let mut removed = vec![];
+        let changed: Vec<_> = self.incident_nets(v).collect();
+        for &e in &changed {
+            if self.e_enabled[e as usize] && self.e[e as usize].1 == 1 {
+                removed.push(self.remove_net(e, None));
+            }
+        }
+
+        // Identical nets have the same size and fingerprint, and a new pair
+        // needs one changed net, so only sizes of changed nets are candidates.
+        // Within a group, each net is merged into the first earlier net with
+        // the same pins.
+        let sizes: Vec<_> = changed
+            .iter()
+            .filter(|e| self.e_enabled[**e as usize])
+            .map(|e| self.e[*e as usize].1)
+            .collect();
+        if sizes.is_empty() {
+            return removed;
+        }
+        let mut candidates: Vec<_> = self
+            .incident_nets(u)
+            .filter(|e| sizes.contains(&self.e[*e as usize].1))
+            .map(|e| {
+                let fingerprint = self
+                    .pins_in_net(e)
+                    .fold(0u64, |fp, p| fp.wrapping_add(mix(p)));
+                (self.e[e as usize].1, fingerprint, e)
+            })
+            .collect();
+        candidates.sort_unstable();
+        for group in candidates.chunk_by(|a, b| (a.0, a.1) == (b.0, b.1)) {
+            for (idx, (_, _, e)) in group.iter().enumerate().skip(1) {
+                let pins = self.sorted_pins(*e);
+                let representative = group[..idx]
+                    .iter()
+                    .find(|(_, _, r)| self.e_enabled[*r as usize] && self.sorted_pins(*r) == pins);
+                if let Some((_, _, r)) = representative {
+                    removed.push(self.remove_net(*e, Some(*r)));
+                }
+            }
+        }
+        removed
+    }
+
+    /// Disables net `e`, taking it out of the nets of its pins, and moves its
+    /// weight to `representative`, if any. Each pin's nets stay contiguous:
+    /// `e` is swapped to the end of the list, right past its new length, so
+    /// `restore_net` only has to grow the list again.
+    fn remove_net(&mut self, e: Index, representative: Option<Index>) -> RemovedNet {
+        for p in self.sorted_pins(e) {
+            let (p_idx, p_len) = self.v[p as usize];
+            let last = p_idx + p_len - 1;
+            let pos = (p_idx..=last)
+                .find(|idx| self.a[*idx as usize] == e)
+                .expect("net missing from the nets of its pin");
+            self.a.swap(pos as usize, last as usize);
+            self.v[p as usize].1 -= 1;
+        }
+        self.e_enabled.set(e as usize, false);
+        let weight = representative.map_or(0.0, |r| self.w[r as usize]);
+        if let Some(r) = representative {
+            self.w[r as usize] += self.w[e as usize];
+        }
+        RemovedNet {
+            e,
+            representative,
+            weight,
+        }
+    }
+
+    /// Undoes `remove_net`. Nets must be restored in the reverse order of
+    /// their removal.
+    fn restore_net(&mut self, removed: RemovedNet) {
+        for p in self.sorted_pins(removed.e).into_iter().rev() {
+            self.v[p as usize].1 += 1;
+        }
+        self.e_enabled.set(removed.e as usize, true);
+        if let Some(r) = removed.representative {
+            self.w[r as usize] = removed.weight;
+        }
+    }
+
+    /// The distinct pins of net `e`, in ascending order.
+    fn sorted_pins(&self, e: Index) -> Vec<Index> {
+        let mut pins: Vec<_> = self.pins_in_net(e).collect();
+        pins.sort_unstable();
+        pins.dedup();
+        pins
     }
 
     /// Implements Algorithm 3: Uncontract from Schlag '2015.
     pub(crate) fn uncontract(&mut self, m: Memento) {
+        for removed in m.removed.into_iter().rev() {
+            self.restore_net(removed);
+        }
         self.v_enabled.set(m.v as usize, true);
         self.num_disabled -= 1;
         let mut b = bitvec![usize, Lsb0; 0; self.e.len()];
@@ -157,31 +395,39 @@ impl Bipartite {
         }
     }
 
+    /// The nets that `v` is a pin of.
     pub(crate) fn incident_nets(
         &self,
         v: Index,
     ) -> impl ExactSizeIterator<Item = Index> + Clone + '_ {
         let v_idx = self.v[v as usize].0 as usize;
         let v_len = self.v[v as usize].1 as usize;
-        self.a[v_idx..v_idx + v_len].iter().map(|x| *x)
+        self.a[v_idx..v_idx + v_len].iter().copied()
     }
 
+    /// The pins of net `e`.
     pub(crate) fn pins_in_net(
         &self,
         e: Index,
     ) -> impl ExactSizeIterator<Item = Index> + Clone + '_ {
         let e_idx = self.e[e as usize].0 as usize;
         let e_len = self.e[e as usize].1 as usize;
-        self.a[e_idx..e_idx + e_len].iter().map(|x| *x)
+        self.a[e_idx..e_idx + e_len].iter().copied()
+    }
+
+    /// Whether every net has at most two pins, i.e. the hypergraph is a graph.
+    pub(crate) fn is_graph(&self) -> bool {
+        self.graph
     }
 
+    /// The pins sharing a net with `v`. A pin may appear more than once.
     pub(crate) fn incident_pins(&self, v: Index) -> impl Iterator<Item = Index> + Clone + '_ {
         self.incident_nets(v)
-            .map(|e| self.pins_in_net(e))
-            .flatten()
+            .flat_map(|e| self.pins_in_net(e))
             .filter(move |u| *u != v)
     }
 
+    /// The summed capacity of all enabled vertices.
     pub(crate) fn total_capacity(&self) -> f32 {
         self.c
             .iter()
@@ -191,14 +437,18 @@ impl Bipartite {
             .sum()
     }
 
+    /// The enabled vertices.
     pub(crate) fn pins(&self) -> impl Iterator<Item = Index> + Clone + '_ {
         (0..self.v.len())
             .filter(|idx| self.v_enabled[*idx])
             .map(|idx| idx as Index)
     }
 
-    pub(crate) fn nets(&self) -> impl Iterator<Item = Index> + Clone {
-        (0..self.e.len()).map(|idx| idx as Index)
+    /// The enabled nets.
+    pub(crate) fn nets(&self) -> impl Iterator<Item = Index> + Clone + '_ {
+        (0..self.e.len())
+            .filter(|idx| self.e_enabled[*idx])
+            .map(|idx| idx as Index)
     }
 
     pub(crate) fn enabled(&self, v: Index) -> bool {
@@ -214,15 +464,23 @@ impl Bipartite {
         self.w[e as usize]
     }
 
+    /// The number of enabled vertices.
     pub(crate) fn num_pins(&self) -> usize {
         self.v.len() - self.num_disabled
     }
 
+    /// One past the largest vertex ID, including disabled vertices.
     pub(crate) fn pin_index_space_size(&self) -> usize {
         self.v.len()
     }
 
+    /// The number of enabled nets.
     pub(crate) fn num_nets(&self) -> usize {
+        self.e_enabled.count_ones()
+    }
+
+    /// One past the largest net ID, including disabled nets.
+    pub(crate) fn net_index_space_size(&self) -> usize {
         self.e.len()
     }
 
@@ -254,15 +512,26 @@ impl Bipartite {
 
     /// Calculate the maximum size for a bipartition, given epsilon.
     pub(crate) fn size_constraint(&self, epsilon: f32) -> f32 {
-        (1.0 + epsilon) * (self.total_capacity() as f32 / 2.0)
+        (1.0 + epsilon) * (self.total_capacity() / 2.0)
     }
 }
 
 /// A bipartition is an assignment of a bool to each vertex.
-pub(crate) type Bipartition = Vec<bool>;
+pub type Bipartition = Vec<bool>;
+
+/// Scrambles a pin ID, so that sums of them make good fingerprints of sets.
+fn mix(p: Index) -> u64 {
+    // The finalizer of SplitMix64.
+    let mut x = (p as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
 
 #[cfg(test)]
 mod tests {
+    use rand::prelude::*;
+
     use super::*;
 
     #[test]
@@ -274,8 +543,10 @@ mod tests {
             a: vec![0, 0, 1, 0, 0, 1, 1, 0, 1, 2, 3, 1, 4, 5],
             v_enabled: bitvec![usize, Lsb0; 1; 6],
             num_disabled: 0,
+            e_enabled: bitvec![usize, Lsb0; 1; 2],
             c: vec![1.0; 6],
             w: vec![1.0; 2],
+            graph: false,
         };
 
         let mut contract = original.clone();
@@ -287,8 +558,10 @@ mod tests {
             a: vec![0, 0, 1, 0, 0, 1, 1, 0, 3, 2, 1, 5, 4, 0, 0, 1],
             v_enabled: bitvec![1, 0, 1, 1, 1, 1],
             num_disabled: 1,
+            e_enabled: bitvec![usize, Lsb0; 1; 2],
             c: vec![2.0, 1.0, 1.0, 1.0, 1.0, 1.0],
             w: vec![1.0; 2],
+            graph: false,
         };
         assert_eq!(contract, correct);
 
@@ -300,9 +573,124 @@ mod tests {
             a: vec![0, 0, 1, 0, 0, 1, 1, 0, 3, 2, 1, 5, 4, 1, 0, 1],
             num_disabled: 0,
             v_enabled: bitvec![usize, Lsb0; 1; 6],
+            e_enabled: bitvec![usize, Lsb0; 1; 2],
             c: vec![1.0; 6],
             w: vec![1.0; 2],
+            graph: false,
         };
         assert_eq!(contract, correct);
     }
+
+    type Nets = Vec<(Index, f32, Vec<Index>)>;
+
+    /// The nets of every enabled vertex and the weight and pins of every
+    /// enabled net, all sorted, which must agree with each other.
+    fn normalized(h: &Bipartite) -> (Vec<Vec<Index>>, Nets) {
+        let incidence: Vec<_> = h
+            .pins()
+            .map(|v| {
+                let mut nets: Vec<_> = h.incident_nets(v).collect();
+                nets.sort_unstable();
+                nets
+            })
+            .collect();
+        let nets: Vec<_> = h
+            .nets()
+            .map(|e| (e, h.weight(e), h.sorted_pins(e)))
+            .collect();
+        for (v, incident) in h.pins().zip(&incidence) {
+            let expected: Vec<_> = nets
+                .iter()
+                .filter(|(_, _, pins)| pins.contains(&v))
+                .map(|(e, _, _)| *e)
+                .collect();
+            assert_eq!(*incident, expected, "nets of {v}");
+        }
+        (incidence, nets)
+    }
+
+    #[test]
+    fn contract_removes_redundant_nets() {
+        let nets: [&[Index]; 4] = [&[0, 2], &[1, 2], &[0, 1], &[0, 1, 3]];
+        let original = Bipartite::new(&[1.0; 4], &[1.0, 2.0, 4.0, 8.0], &nets);
+        let mut h = original.clone();
+        let m = h.contract(0, 1);
+        // Net 1 became identical to net 0, and net 2 lost all but one pin.
+        assert_eq!(
+            normalized(&h).1,
+            [(0, 3.0, vec![0, 2]), (3, 8.0, vec![0, 3])]
+        );
+        assert_eq!(h.num_nets(), 2);
+        h.uncontract(m);
+        assert_eq!(normalized(&h), normalized(&original));
+    }
+
+    #[test]
+    fn uncontract_restores_removed_nets() {
+        // Random contractions along nets, undone in reverse order.
+        let rng = &mut StdRng::seed_from_u64(1);
+        let nets: Vec<Vec<Index>> = (0..60)
+            .map(|_| {
+                (0..rng.gen_range(2..5))
+                    .map(|_| rng.gen_range(0..30))
+                    .collect()
+            })
+            .map(|mut net: Vec<_>| {
+                net.sort_unstable();
+                net.dedup();
+                net
+            })
+            .collect();
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let weights: Vec<_> = (0..nets.len()).map(|e| e as f32).collect();
+        let original = Bipartite::new(&[1.0; 30], &weights, &nets);
+
+        let mut h = original.clone();
+        let mut states = vec![];
+        let mut mementos = vec![];
+        loop {
+            let connected: Vec<_> = h
+                .pins()
+                .filter(|u| h.incident_pins(*u).next().is_some())
+                .collect();
+            let Some(u) = connected.choose(rng).copied() else {
+                break;
+            };
+            states.push(normalized(&h));
+            let v = h.incident_pins(u).choose(rng).unwrap();
+            mementos.push(h.contract(u, v));
+        }
+        assert!(h.num_nets() == 0 && mementos.len() > 10);
+        while let Some(m) = mementos.pop() {
+            h.uncontract(m);
+            assert_eq!(normalized(&h), states.pop().unwrap());
+        }
+        assert_eq!(normalized(&h), normalized(&original));
+    }
+
+    #[test]
+    fn transpose_nets() {
+        let nets: [&[Index]; 3] = [&[0, 1], &[2, 1, 2], &[]];
+        let h = Bipartite::new(&[1.0; 4], &[1.0; 3], &nets);
+        assert_eq!(h.incident_nets(0).collect::<Vec<_>>(), [0]);
+        assert_eq!(h.incident_nets(1).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(h.incident_nets(2).collect::<Vec<_>>(), [1]);
+        assert_eq!(h.incident_nets(3).count(), 0);
+
+        let nets: [&[Index]; 3] = [&[0, 1], &[2, 1], &[]];
+        let vptr = [0, 1, 3, 4, 4];
+        let vind = [0, 0, 1, 1];
+        assert_eq!(
+            Bipartite::with_incidence(&[1.0; 4], &[1.0; 3], &nets, &vptr, &vind),
+            Bipartite::new(&[1.0; 4], &[1.0; 3], &nets)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "vind doesn't match the nets at vertex 1")]
+    fn mismatched_incidence() {
+        // The lengths add up, but vertex 1 claims net 2 instead of net 1.
+        let nets: [&[Index]; 3] = [&[0, 1], &[2, 1], &[]];
+        Bipartite::with_incidence(&[1.0; 4], &[1.0; 3], &nets, &[0, 1, 3, 4, 4], &[0, 0, 2, 1]);
+    }
 }
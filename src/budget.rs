@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config::Config;
+
+/// Cancels a running partitioner from another thread. The partitioner stops at
+/// its next check and returns the best partition it has.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The time limit and cancellation token of a run. Long loops poll
+/// `exhausted`, and wrap up as soon as it returns true.
+#[derive(Debug)]
+pub(crate) struct Budget {
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    exhausted: AtomicBool,
+}
+
+impl Budget {
+    pub(crate) fn new(config: &Config) -> Self {
+        Budget {
+            deadline: config.time_limit.map(|limit| Instant::now() + limit),
+            cancellation: config.cancellation.clone(),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns whether the run is out of time or has been cancelled. Once this
+    /// returns true, it keeps doing so.
+    pub(crate) fn exhausted(&self) -> bool {
+        if self.exhausted.load(Ordering::Relaxed) {
+            return true;
+        }
+        let exhausted = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled);
+        if exhausted {
+            self.exhausted.store(true, Ordering::Relaxed);
+        }
+        exhausted
+    }
+
+    /// Whether any phase was cut short.
+    pub(crate) fn truncated(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+}
@@ -2,67 +2,293 @@ use std::collections::BinaryHeap;
 
 use bitvec::prelude::*;
 use ordered_float::OrderedFloat;
+use rand::prelude::*;
+use rayon::prelude::*;
 
 use crate::bipartite::*;
+use crate::budget::Budget;
+use crate::config::Clustering;
+use crate::observer::{Event, Progress, BATCH_SIZE};
 
-pub(crate) fn coarsen(h: &mut Bipartite) -> Vec<Memento> {
+pub(crate) fn coarsen(h: &mut Bipartite, budget: &Budget, progress: &Progress) -> Vec<Memento> {
     let c_max = S * h.total_capacity() / T as f32;
 
-    let mut pq = BinaryHeap::new();
-    for u in h.pins() {
-        if h.capacity(u) > c_max {
-            continue;
-        }
-
-        let Some(v) = h
-            .incident_pins(u)
-            .max_by_key(|v| OrderedFloat(rate(h, u, *v)))
-        else {
-            continue;
-        };
-        pq.push((OrderedFloat(rate(h, u, v)), (u, v)));
-    }
+    let pins: Vec<_> = h.pins().collect();
+    let mut pq: BinaryHeap<_> = pins
+        .into_par_iter()
+        .filter(|u| h.capacity(*u) <= c_max && !budget.exhausted())
+        .filter_map(|u| {
+            let (rating, v) = best_neighbor(h, u)?;
+            Some((rating, (u, v)))
+        })
+        .collect();
     let mut removed = bitvec![usize, Lsb0; 0; h.pin_index_space_size()];
     let mut invalid = bitvec![usize, Lsb0; 0; h.pin_index_space_size()];
 
     let mut mementos = vec![];
-    while let Some((_, (u, v))) = pq.pop()
-        && h.num_pins() >= T
-    {
-        if removed[u as usize] {
+    while let Some((_, (u, v))) = pq.pop() {
+        if h.num_pins() < T || budget.exhausted() {
+            break;
+        } else if removed[u as usize] {
             continue;
         } else if invalid[u as usize] {
-            let v = h
-                .incident_pins(u)
-                .max_by_key(|v| OrderedFloat(rate(h, u, *v)))
-                .unwrap();
-            pq.push((OrderedFloat(rate(h, u, v)), (u, v)));
+            if let Some((rating, v)) = best_neighbor(h, u) {
+                pq.push((rating, (u, v)));
+            }
             invalid.set(u as usize, false);
             continue;
         }
 
         mementos.push(h.contract(u, v));
         removed.set(v as usize, true);
+        if mementos.len().is_multiple_of(BATCH_SIZE) {
+            progress.notify(|| coarsening_event(h));
+        }
 
         for n in h.incident_pins(u) {
             invalid.set(n as usize, true);
         }
     }
+    progress.notify(|| coarsening_event(h));
 
     mementos
 }
 
+fn coarsening_event(h: &Bipartite) -> Event {
+    Event::Coarsening {
+        num_vertices: h.num_pins(),
+        target: T,
+    }
+}
+
 /// Constants from Section 5 from Schlag '2015.
 const T: usize = 100;
 const S: f32 = 3.25;
 
+/// The highest rated neighbor of `u`, with its rating.
+fn best_neighbor(h: &Bipartite, u: Index) -> Option<(OrderedFloat<f32>, Index)> {
+    if h.is_graph() {
+        return best_graph_neighbor(h, u);
+    }
+    let v = h
+        .incident_pins(u)
+        .max_by_key(|v| OrderedFloat(rate(h, u, *v)))?;
+    Some((OrderedFloat(rate(h, u, v)), v))
+}
+
+/// Fast path of `best_neighbor` for graphs. Every net is an edge, so a single
+/// pass over the edges of `u` sums up the heavy-edge rating of all neighbors,
+/// instead of scanning all nets for every neighbor.
+fn best_graph_neighbor(h: &Bipartite, u: Index) -> Option<(OrderedFloat<f32>, Index)> {
+    let mut edges: Vec<_> = h
+        .incident_nets(u)
+        .filter_map(|e| Some((h.pins_in_net(e).find(|v| *v != u)?, h.weight(e))))
+        .collect();
+    edges.sort_unstable_by_key(|(v, _)| *v);
+    edges
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|edges| {
+            let v = edges[0].0;
+            let weight: f32 = edges.iter().map(|(_, w)| w).sum();
+            (OrderedFloat(weight / (h.capacity(u) * h.capacity(v))), v)
+        })
+        .max()
+}
+
 fn rate(h: &Bipartite, u: Index, v: Index) -> f32 {
     let inv_c = 1.0 / (h.capacity(u) * h.capacity(v));
     let mut heavy_edge = 0.0;
-    for e in h.nets() {
-        if h.pins_in_net(e).any(|p| p == u) && h.pins_in_net(e).any(|p| p == u) {
+    for e in h.incident_nets(u) {
+        if h.pins_in_net(e).any(|p| p == v) {
             heavy_edge += h.weight(e) / (h.pins_in_net(e).len() - 1) as f32;
         }
     }
     inv_c * heavy_edge
 }
+
+/// One level of multilevel coarsening: the contracted hypergraph, and the map
+/// from each vertex of the previous (finer) level to its vertex in `h`.
+pub(crate) struct Level {
+    pub(crate) h: Bipartite,
+    pub(crate) map: Vec<Index>,
+}
+
+/// Coarsening stops once a pass fails to shrink the hypergraph by this factor.
+const MIN_SHRINK: f32 = 0.95;
+
+/// Coarsens by repeatedly clustering vertices and contracting every cluster at
+/// once. If `restriction` is given, only vertices with the same restriction
+/// label are clustered together. Returns the levels from finest to coarsest.
+pub(crate) fn coarsen_multilevel(
+    h: &Bipartite,
+    clustering: Clustering,
+    restriction: Option<&[u32]>,
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut impl Rng,
+) -> Vec<Level> {
+    let c_max = S * h.total_capacity() / T as f32;
+
+    let mut levels: Vec<Level> = vec![];
+    let mut restriction = restriction.map(<[_]>::to_vec);
+    loop {
+        let fine = levels.last().map_or(h, |level| &level.h);
+        if fine.num_pins() < T || budget.exhausted() {
+            break;
+        }
+
+        let (map, num_clusters) = cluster(fine, clustering, c_max, restriction.as_deref(), rng);
+        if num_clusters as f32 > MIN_SHRINK * fine.num_pins() as f32 {
+            break;
+        }
+        let coarse = fine.contract_clusters(&map, num_clusters);
+        if let Some(labels) = &mut restriction {
+            let mut coarse_labels = vec![0; num_clusters];
+            for v in fine.pins() {
+                coarse_labels[map[v as usize] as usize] = labels[v as usize];
+            }
+            *labels = coarse_labels;
+        }
+        progress.notify(|| coarsening_event(&coarse));
+        levels.push(Level { h: coarse, map });
+    }
+
+    levels
+}
+
+/// Number of rated neighbors kept per vertex when clustering.
+const CANDIDATES: usize = 4;
+
+/// Groups vertices into clusters using the heavy-edge rating. Ratings are
+/// computed in parallel, then clusters are formed sequentially from each
+/// vertex's best candidates. Returns the dense cluster ID of every vertex and
+/// the number of clusters.
+fn cluster(
+    h: &Bipartite,
+    clustering: Clustering,
+    c_max: f32,
+    restriction: Option<&[u32]>,
+    rng: &mut impl Rng,
+) -> (Vec<Index>, usize) {
+    const UNCLUSTERED: Index = Index::MAX;
+    let mut clusters = vec![UNCLUSTERED; h.pin_index_space_size()];
+    let mut cluster_capacity = vec![];
+
+    let pins: Vec<_> = h.pins().collect();
+    let candidates: Vec<_> = pins
+        .par_iter()
+        .map_init(
+            || (vec![0.0; h.pin_index_space_size()], vec![]),
+            |(ratings, touched), u| rate_neighbors(h, *u, c_max, restriction, ratings, touched),
+        )
+        .collect();
+
+    let mut order: Vec<_> = (0..pins.len()).collect();
+    order.shuffle(rng);
+    for idx in order {
+        let u = pins[idx];
+        if clusters[u as usize] != UNCLUSTERED {
+            continue;
+        }
+
+        let c_u = h.capacity(u);
+        let best = candidates[idx]
+            .iter()
+            .copied()
+            .find(|v| match clusters[*v as usize] {
+                UNCLUSTERED => true,
+                _ if clustering == Clustering::Matching => false,
+                cluster => c_u + cluster_capacity[cluster as usize] <= c_max,
+            });
+
+        let cluster = match best {
+            Some(v) if clusters[v as usize] != UNCLUSTERED => clusters[v as usize],
+            Some(v) => {
+                let cluster = cluster_capacity.len() as Index;
+                cluster_capacity.push(h.capacity(v));
+                clusters[v as usize] = cluster;
+                cluster
+            }
+            None => {
+                cluster_capacity.push(0.0);
+                cluster_capacity.len() as Index - 1
+            }
+        };
+        clusters[u as usize] = cluster;
+        cluster_capacity[cluster as usize] += c_u;
+    }
+
+    (clusters, cluster_capacity.len())
+}
+
+/// Returns the best rated neighbors of `u` that fit with `u` under `c_max` and
+/// share its restriction label, best first. `ratings` and `touched` are
+/// scratch space, and are left cleared.
+fn rate_neighbors(
+    h: &Bipartite,
+    u: Index,
+    c_max: f32,
+    restriction: Option<&[u32]>,
+    ratings: &mut [f32],
+    touched: &mut Vec<Index>,
+) -> Vec<Index> {
+    for e in h.incident_nets(u) {
+        let score = h.weight(e) / (h.pins_in_net(e).len() - 1) as f32;
+        for v in h.pins_in_net(e).filter(|v| *v != u) {
+            if ratings[v as usize] == 0.0 {
+                touched.push(v);
+            }
+            ratings[v as usize] += score;
+        }
+    }
+
+    let c_u = h.capacity(u);
+    let mut rated: Vec<_> = touched
+        .iter()
+        .copied()
+        .filter(|v| c_u + h.capacity(*v) <= c_max)
+        .filter(|v| restriction.is_none_or(|labels| labels[u as usize] == labels[*v as usize]))
+        .map(|v| (OrderedFloat(ratings[v as usize] / (c_u * h.capacity(v))), v))
+        .collect();
+    rated.sort_unstable_by(|a, b| b.cmp(a));
+    rated.dedup_by_key(|(_, v)| *v);
+    for v in touched.drain(..) {
+        ratings[v as usize] = 0.0;
+    }
+
+    rated.into_iter().take(CANDIDATES).map(|(_, v)| v).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_rating_matches_rate() {
+        // A weighted graph with a parallel edge and uneven capacities.
+        let edges: [(Index, Index, f32); 7] = [
+            (0, 1, 1.0),
+            (0, 2, 3.0),
+            (1, 2, 2.0),
+            (2, 3, 1.5),
+            (3, 4, 4.0),
+            (2, 3, 0.75),
+            (4, 0, 0.5),
+        ];
+        let nets: Vec<_> = edges.iter().map(|(u, v, _)| [*u, *v]).collect();
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let weights: Vec<_> = edges.iter().map(|(_, _, w)| *w).collect();
+        let h = Bipartite::new(&[1.0, 2.0, 1.5, 1.0, 3.0], &weights, &nets);
+        assert!(h.is_graph());
+
+        for u in h.pins() {
+            let (OrderedFloat(rating), v) = best_graph_neighbor(&h, u).unwrap();
+            let best = h
+                .incident_pins(u)
+                .map(|v| rate(&h, u, v))
+                .fold(f32::NEG_INFINITY, f32::max);
+            assert!((rating - best).abs() < 1e-6, "{u}: {rating} != {best}");
+            assert_eq!(rating, rate(&h, u, v));
+        }
+    }
+}
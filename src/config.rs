@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rayon::ThreadPool;
+
+use crate::budget::CancellationToken;
+use crate::initial::{default_portfolio, InitialPartitioner};
+use crate::observer::Observer;
+
+/// Configuration for a partitioning run. With the `serde` feature, the
+/// fields that can't be serialized are skipped, and missing fields are
+/// deserialized to their defaults.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Config {
+    /// Allowed imbalance. Each block may hold at most `(1 + epsilon) / 2` of
+    /// the total capacity.
+    pub epsilon: f32,
+    pub coarsening: Coarsening,
+    /// Refinement used when uncoarsening multilevel hierarchies. N-level
+    /// uncoarsening always runs localized FM after each uncontraction.
+    pub refinement: Refinement,
+    /// Objective minimized by k-way partitioning. Bisection always minimizes
+    /// the cut, which is the same as the connectivity metric for two blocks.
+    pub objective: Objective,
+    /// Drop single-pin nets and merge identical nets before partitioning.
+    /// Coarsening always does the same for the nets its contractions make
+    /// redundant, after every contraction with n-level coarsening and on every
+    /// level with multilevel coarsening.
+    pub remove_redundant_nets: bool,
+    /// Nets with more pins than this are removed before partitioning. They
+    /// are restored afterwards, so their weight still counts towards the
+    /// reported objectives.
+    pub max_net_size: Option<usize>,
+    /// Initial partitioning algorithms, each with its number of runs.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub portfolio: Vec<(Arc<dyn InitialPartitioner>, usize)>,
+    /// Exact initial partitioning of small coarse hypergraphs.
+    pub exact: Option<Exact>,
+    /// Run a memetic algorithm in each try instead of a single multilevel
+    /// pass. Much slower, but gives the best cuts.
+    pub evolutionary: Option<Evolutionary>,
+    /// Number of independently seeded tries. The best feasible one is kept.
+    pub repetitions: usize,
+    /// Whether tries run in parallel with each other.
+    pub parallel_repetitions: bool,
+    /// Seed for all random choices. Runs with the same seed and config give
+    /// the same result. Seeded from entropy if unset.
+    pub seed: Option<u64>,
+    /// Pool to run the parallel phases on. Uses the global rayon pool if
+    /// unset.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub thread_pool: Option<Arc<ThreadPool>>,
+    /// Wall-clock budget for the whole call, preprocessing included. When it
+    /// runs out, the best partition found so far is returned and marked as
+    /// truncated.
+    pub time_limit: Option<Duration>,
+    /// Stops the run early, like running out of `time_limit`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cancellation: Option<CancellationToken>,
+    /// Receives progress events during the run.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub observer: Option<Arc<dyn Observer>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            epsilon: 0.03,
+            coarsening: Coarsening::NLevel,
+            refinement: Refinement::Fm,
+            objective: Objective::Km1,
+            remove_redundant_nets: true,
+            max_net_size: None,
+            portfolio: default_portfolio(),
+            exact: None,
+            evolutionary: None,
+            repetitions: 1,
+            parallel_repetitions: false,
+            seed: None,
+            thread_pool: None,
+            time_limit: None,
+            cancellation: None,
+            observer: None,
+        }
+    }
+}
+
+/// How the hypergraph is coarsened before initial partitioning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Coarsening {
+    /// Contract a single pair of vertices per level, as in Schlag '2015. Gives
+    /// the best quality, but produces up to |V| levels.
+    NLevel,
+    /// Contract whole clusters per level, building an explicit smaller
+    /// hypergraph each time. Much faster and lighter on large inputs.
+    Multilevel(Clustering),
+}
+
+/// How vertices are grouped during a multilevel coarsening pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Clustering {
+    /// Each vertex is paired with at most one other vertex.
+    Matching,
+    /// Each vertex joins the cluster of its highest rated neighbor.
+    HeavyEdge,
+}
+
+/// How a partition is improved after being projected onto a finer level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Refinement {
+    /// A sequential FM pass over all border vertices.
+    Fm,
+    /// Rounds of parallel label propagation.
+    LabelPropagation,
+}
+
+/// What k-way partitioning minimizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Objective {
+    /// Total weight of the nets spanning more than one block. During
+    /// recursive bisection, cut nets are removed from both halves.
+    Cut,
+    /// Sum over the nets of their weight times the number of blocks they span
+    /// minus one. During recursive bisection, cut nets are split between the
+    /// halves.
+    Km1,
+}
+
+/// A starting point for the configuration, trading speed for quality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Preset {
+    /// Multilevel heavy-edge coarsening with label propagation refinement.
+    Fast,
+    /// `Config::default()`: n-level coarsening with FM refinement.
+    Default,
+    /// N-level coarsening with FM refinement, and exact initial partitioning
+    /// of small coarse hypergraphs.
+    Quality,
+}
+
+impl From<Preset> for Config {
+    fn from(preset: Preset) -> Self {
+        let (coarsening, refinement, exact) = match preset {
+            Preset::Fast => (
+                Coarsening::Multilevel(Clustering::HeavyEdge),
+                Refinement::LabelPropagation,
+                None,
+            ),
+            Preset::Default => return Config::default(),
+            Preset::Quality => (
+                Coarsening::NLevel,
+                Refinement::Fm,
+                Some(Exact {
+                    max_vertices: 40,
+                    time_limit: Duration::from_secs(1),
+                }),
+            ),
+        };
+        Config {
+            coarsening,
+            refinement,
+            exact,
+            ..Config::default()
+        }
+    }
+}
+
+/// Settings for exact initial partitioning by branch-and-bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Exact {
+    /// Only used if the coarsest hypergraph has at most this many vertices.
+    pub max_vertices: usize,
+    /// Once this runs out, the best bipartition found so far is used.
+    pub time_limit: Duration,
+}
+
+/// Settings for memetic partitioning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Evolutionary {
+    pub population_size: usize,
+    /// The search returns the best individual once this runs out.
+    pub time_limit: Duration,
+}
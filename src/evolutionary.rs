@@ -0,0 +1,294 @@
+use std::time::Instant;
+
+use ordered_float::OrderedFloat;
+use rand::prelude::*;
+
+use crate::bipartite::*;
+use crate::budget::Budget;
+use crate::coarsen::{coarsen_multilevel, Level};
+use crate::config::{Clustering, Coarsening, Config, Evolutionary};
+use crate::initial::initial_partitioning;
+use crate::observer::Progress;
+use crate::run;
+use crate::uncoarsen::{refine, uncoarsen_multilevel};
+
+/// Probability that an offspring is created by mutation rather than by
+/// recombination.
+const MUTATION_PROBABILITY: f32 = 0.2;
+
+struct Individual {
+    partition: Bipartition,
+    fitness: (OrderedFloat<f32>, OrderedFloat<f32>),
+    // Sorted IDs of the cut nets, used to measure diversity.
+    cut_nets: Vec<Index>,
+}
+
+impl Individual {
+    fn new(h: &Bipartite, size_constraint: f32, partition: Bipartition) -> Self {
+        let (imbalance, cut) = h.evaluate_bipartition(&partition);
+        let cut_nets = h
+            .nets()
+            .filter(|e| {
+                let mut blocks = h.pins_in_net(*e).map(|v| partition[v as usize]);
+                blocks
+                    .next()
+                    .is_some_and(|first| blocks.any(|block| block != first))
+            })
+            .collect();
+        Individual {
+            partition,
+            fitness: (
+                OrderedFloat(imbalance.max(size_constraint)),
+                OrderedFloat(cut),
+            ),
+            cut_nets,
+        }
+    }
+
+    /// Size of the symmetric difference of the cut net sets.
+    fn distance(&self, other: &Individual) -> usize {
+        let (mut i, mut j) = (0, 0);
+        let mut common = 0;
+        while i < self.cut_nets.len() && j < other.cut_nets.len() {
+            match self.cut_nets[i].cmp(&other.cut_nets[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    common += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        self.cut_nets.len() + other.cut_nets.len() - 2 * common
+    }
+}
+
+/// Memetic partitioning in the style of KaHyPar-E. A population is seeded by
+/// regular multilevel runs, then evolved until the time limit by
+/// recombination and mutation, both of which are V-cycles: the hypergraph is
+/// coarsened without contracting across the blocks of the parents. Individuals
+/// are ranked by their objective on `original`, which `h` may be a
+/// preprocessed version of. Evolution also stops once the budget is
+/// exhausted.
+pub(crate) fn evolve(
+    original: &Bipartite,
+    h: &Bipartite,
+    config: &Config,
+    evolutionary: &Evolutionary,
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let deadline = Instant::now() + evolutionary.time_limit;
+    let running = || Instant::now() < deadline && !budget.exhausted();
+    let size_constraint = h.size_constraint(config.epsilon);
+
+    let mut population = vec![];
+    while population.is_empty() || population.len() < evolutionary.population_size && running() {
+        let partition = run(h, config, budget, progress, rng);
+        population.push(Individual::new(original, size_constraint, partition));
+    }
+
+    while running() {
+        let partition = if population.len() < 2 || rng.gen::<f32>() < MUTATION_PROBABILITY {
+            let parent = &population[rng.gen_range(0..population.len())];
+            mutate(h, config, parent, budget, progress, rng)
+        } else {
+            let p1 = tournament(&population, None, rng);
+            let p2 = tournament(&population, Some(p1), rng);
+            recombine(
+                h,
+                config,
+                &population[p1],
+                &population[p2],
+                budget,
+                progress,
+                rng,
+            )
+        };
+        let child = Individual::new(original, size_constraint, partition);
+
+        // The child replaces the most similar individual that is no better than
+        // it, which keeps the population from collapsing onto one solution.
+        let replaced = population
+            .iter()
+            .enumerate()
+            .filter(|(_, individual)| individual.fitness >= child.fitness)
+            .min_by_key(|(_, individual)| individual.distance(&child))
+            .map(|(idx, _)| idx);
+        if let Some(idx) = replaced {
+            population[idx] = child;
+        }
+    }
+
+    population
+        .into_iter()
+        .min_by_key(|individual| individual.fitness)
+        .unwrap()
+        .partition
+}
+
+/// Binary tournament selection, never picking `exclude`.
+fn tournament(population: &[Individual], exclude: Option<usize>, rng: &mut impl Rng) -> usize {
+    let mut pick = || loop {
+        let idx = rng.gen_range(0..population.len());
+        if Some(idx) != exclude {
+            return idx;
+        }
+    };
+    let (a, b) = (pick(), pick());
+    if population[a].fitness <= population[b].fitness {
+        a
+    } else {
+        b
+    }
+}
+
+/// Contracts only vertices that share a block in both parents, starts from the
+/// better parent at the coarsest level, and refines on the way back up. The
+/// child is at least as good as the better parent.
+fn recombine(
+    h: &Bipartite,
+    config: &Config,
+    p1: &Individual,
+    p2: &Individual,
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let restriction: Vec<_> = (0..h.pin_index_space_size())
+        .map(|v| p1.partition[v] as u32 * 2 + p2.partition[v] as u32)
+        .collect();
+    let better = if p1.fitness <= p2.fitness { p1 } else { p2 };
+    v_cycle(
+        h,
+        config,
+        &restriction,
+        budget,
+        progress,
+        rng,
+        |levels, _, _| project(levels, &better.partition),
+    )
+}
+
+/// Contracts only vertices that share a block in the parent, then partitions
+/// the coarsest level from scratch and refines on the way back up. The child
+/// keeps the parent's clusters together, but may place them differently.
+fn mutate(
+    h: &Bipartite,
+    config: &Config,
+    parent: &Individual,
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let restriction: Vec<_> = parent.partition.iter().map(|b| *b as u32).collect();
+    v_cycle(
+        h,
+        config,
+        &restriction,
+        budget,
+        progress,
+        rng,
+        |_, coarsest, rng| initial_partitioning(coarsest, config, budget, rng),
+    )
+}
+
+/// Coarsens `h` under `restriction`, takes the partition `initial` returns for
+/// the coarsest level, and refines it on the way back up.
+fn v_cycle<R: Rng>(
+    h: &Bipartite,
+    config: &Config,
+    restriction: &[u32],
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut R,
+    initial: impl FnOnce(&[Level], &Bipartite, &mut R) -> Bipartition,
+) -> Bipartition {
+    let clustering = match config.coarsening {
+        Coarsening::Multilevel(clustering) => clustering,
+        Coarsening::NLevel => Clustering::HeavyEdge,
+    };
+    let size_constraint = h.size_constraint(config.epsilon);
+    let levels = progress.time(
+        |timings| &mut timings.coarsening,
+        || coarsen_multilevel(h, clustering, Some(restriction), budget, progress, rng),
+    );
+    let coarsest = levels.last().map_or(h, |level| &level.h);
+    let partition = progress.time(
+        |timings| &mut timings.initial,
+        || {
+            let mut partition = initial(&levels, coarsest, rng);
+            refine(coarsest, size_constraint, &mut partition, budget);
+            partition
+        },
+    );
+    progress.time(
+        |timings| &mut timings.refinement,
+        || {
+            uncoarsen_multilevel(
+                h,
+                size_constraint,
+                &levels,
+                config.refinement,
+                budget,
+                progress,
+                partition,
+            )
+        },
+    )
+}
+
+/// Projects a partition of the finest level onto the coarsest one. Only valid
+/// if no cluster spans both blocks.
+fn project(levels: &[Level], partition: &Bipartition) -> Bipartition {
+    let mut partition = partition.clone();
+    for level in levels {
+        let mut coarse = vec![false; level.h.pin_index_space_size()];
+        for (v, cluster) in level.map.iter().enumerate() {
+            if let Some(block) = coarse.get_mut(*cluster as usize) {
+                *block = partition[v];
+            }
+        }
+        partition = coarse;
+    }
+    partition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutation_changes_partition() {
+        // Eight cliques of 20 vertices in a ring, joined by single nets. The
+        // four best bisections each cut a different pair of joining nets.
+        let mut nets = vec![];
+        for clique in 0..8 {
+            let vertices: Vec<Index> = (clique * 20..clique * 20 + 20).collect();
+            for (idx, u) in vertices.iter().enumerate() {
+                nets.extend(vertices[idx + 1..].iter().map(|v| vec![*u, *v]));
+            }
+            nets.push(vec![clique * 20, (clique + 1) % 8 * 20 + 1]);
+        }
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let h = Bipartite::new(&[1.0; 160], &vec![1.0; nets.len()], &nets);
+        let config = Config {
+            coarsening: Coarsening::Multilevel(Clustering::HeavyEdge),
+            ..Config::default()
+        };
+        let budget = Budget::new(&config);
+        let progress = Progress::new(&config);
+        let size_constraint = h.size_constraint(config.epsilon);
+
+        let rng = &mut StdRng::seed_from_u64(0);
+        let parent = run(&h, &config, &budget, &progress, rng);
+        let parent = Individual::new(&h, size_constraint, parent);
+        let changed = (0..10).any(|_| {
+            let child = mutate(&h, &config, &parent, &budget, &progress, rng);
+            Individual::new(&h, size_constraint, child).distance(&parent) > 0
+        });
+        assert!(changed);
+    }
+}
@@ -0,0 +1,337 @@
+//! C API, declared in `include/hpart.h` and compiled with the `capi` feature.
+//! No panic crosses the boundary: every function catches them and reports
+//! `HpartStatus::Panic` instead.
+
+use std::ffi::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::slice;
+
+use crate::bipartite::Index;
+use crate::config::Config;
+use crate::hypergraph::Hypergraph;
+use crate::kway::partition;
+
+/// Outcome of a call to the C API.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HpartStatus {
+    Ok = 0,
+    /// A pointer was null, `k` was zero, or the CSR arrays were inconsistent.
+    InvalidArgument = 1,
+    /// An internal error. Please report it.
+    Panic = 2,
+}
+
+/// A hypergraph owned by the library.
+pub struct HpartHypergraph(Hypergraph);
+
+/// Creates a hypergraph from hMETIS-style CSR arrays: the pins of net `e` are
+/// `eind[eptr[e]..eptr[e + 1]]`. `capacities` and `weights` may be null for
+/// unit capacities and weights. The arrays are copied. On success, stores
+/// the hypergraph in `*out`, to be freed with `hpart_hypergraph_free`.
+///
+/// # Safety
+///
+/// `eptr` must hold `num_nets + 1` elements, `eind` `eptr[num_nets]`,
+/// `capacities` `num_vertices` and `weights` `num_nets`. `out` must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hpart_hypergraph_new(
+    num_vertices: usize,
+    num_nets: usize,
+    eptr: *const usize,
+    eind: *const Index,
+    capacities: *const f32,
+    weights: *const f32,
+    out: *mut *mut HpartHypergraph,
+) -> HpartStatus {
+    guard(|| {
+        let eptr_len = num_nets
+            .checked_add(1)
+            .ok_or(HpartStatus::InvalidArgument)?;
+        let eptr = as_slice(eptr, eptr_len).ok_or(HpartStatus::InvalidArgument)?;
+        let eind = as_slice(eind, eptr[num_nets]).ok_or(HpartStatus::InvalidArgument)?;
+        let capacities = match as_slice(capacities, num_vertices) {
+            Some(capacities) => capacities.to_vec(),
+            None => vec![1.0; num_vertices],
+        };
+        let weights = match as_slice(weights, num_nets) {
+            Some(weights) => weights.to_vec(),
+            None => vec![1.0; num_nets],
+        };
+        if out.is_null() {
+            return Err(HpartStatus::InvalidArgument);
+        }
+        let h = Hypergraph::from_parts(capacities, weights, eptr.to_vec(), eind.to_vec())
+            .map_err(|_| HpartStatus::InvalidArgument)?;
+        *out = Box::into_raw(Box::new(HpartHypergraph(h)));
+        Ok(())
+    })
+}
+
+/// Frees a hypergraph created by `hpart_hypergraph_new`. Does nothing if `h`
+/// is null.
+///
+/// # Safety
+///
+/// `h` must be null or come from `hpart_hypergraph_new`, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn hpart_hypergraph_free(h: *mut HpartHypergraph) {
+    if !h.is_null() {
+        drop(Box::from_raw(h));
+    }
+}
+
+/// Partitions `h` into `k` blocks with the default configuration, allowing
+/// each block `(1 + epsilon) / k` of the total capacity. Runs with the same
+/// seed give the same result. Stores the block of every vertex in `blocks`,
+/// and the cut and connectivity metric in `*cut` and `*km1` unless they are
+/// null.
+///
+/// # Safety
+///
+/// `h` must come from `hpart_hypergraph_new`. `blocks` must be valid for
+/// writing one element per vertex, and `cut` and `km1` for one element each
+/// unless they are null.
+#[no_mangle]
+pub unsafe extern "C" fn hpart_partition(
+    h: *const HpartHypergraph,
+    k: usize,
+    epsilon: f32,
+    seed: u64,
+    blocks: *mut u32,
+    cut: *mut f32,
+    km1: *mut f32,
+) -> HpartStatus {
+    guard(|| {
+        let h = &h.as_ref().ok_or(HpartStatus::InvalidArgument)?.0;
+        if k == 0 || blocks.is_null() {
+            return Err(HpartStatus::InvalidArgument);
+        }
+        let config = Config {
+            epsilon,
+            seed: Some(seed),
+            ..Config::default()
+        };
+        let result = partition(h, k, &config);
+        slice::from_raw_parts_mut(blocks, h.num_vertices()).copy_from_slice(&result.blocks);
+        if !cut.is_null() {
+            *cut = result.cut;
+        }
+        if !km1.is_null() {
+            *km1 = result.km1;
+        }
+        Ok(())
+    })
+}
+
+/// Drop-in replacement for `HMETIS_PartRecursive` of hMETIS 1.5. Every
+/// bisection leaves each side between `50 - ubfactor` and `50 + ubfactor`
+/// percent of the capacity. If `options[0]` is nonzero, `options[1]` is the
+/// number of tries and `options[7]` the seed, or -1 for a random one; the
+/// other options are ignored. `vwgts` and `hewgts` may be null for unit
+/// weights. Stores the block of every vertex in `part` and the total weight
+/// of the cut nets in `*edgecut`, or -1 in `*edgecut` if the arguments are
+/// invalid.
+///
+/// # Safety
+///
+/// The arrays must be as documented by hMETIS: `eptr` with `nhedges + 1`
+/// elements, `eind` with `eptr[nhedges]`, `vwgts` and `part` with `nvtxs`,
+/// `hewgts` with `nhedges`, and `options` with 9. `options` may be null for
+/// the defaults.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "C" fn HMETIS_PartRecursive(
+    nvtxs: c_int,
+    nhedges: c_int,
+    vwgts: *const c_int,
+    eptr: *const c_int,
+    eind: *const c_int,
+    hewgts: *const c_int,
+    nparts: c_int,
+    ubfactor: c_int,
+    options: *const c_int,
+    part: *mut c_int,
+    edgecut: *mut c_int,
+) {
+    let status = guard(|| {
+        let invalid = HpartStatus::InvalidArgument;
+        let num_vertices = usize::try_from(nvtxs).map_err(|_| invalid)?;
+        let num_nets = usize::try_from(nhedges).map_err(|_| invalid)?;
+        let k = usize::try_from(nparts).map_err(|_| invalid)?;
+        if k == 0 || !(1..50).contains(&ubfactor) || part.is_null() || edgecut.is_null() {
+            return Err(invalid);
+        }
+        let eptr = as_slice(eptr, num_nets.checked_add(1).ok_or(invalid)?).ok_or(invalid)?;
+        let eptr: Vec<usize> = eptr
+            .iter()
+            .map(|x| usize::try_from(*x))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid)?;
+        let eind = as_slice(eind, eptr[num_nets]).ok_or(invalid)?;
+        let eind: Vec<Index> = eind
+            .iter()
+            .map(|x| Index::try_from(*x))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid)?;
+        let to_f32 = |weights: Option<&[c_int]>, len| match weights {
+            Some(weights) => weights.iter().map(|w| *w as f32).collect(),
+            None => vec![1.0; len],
+        };
+        let capacities = to_f32(as_slice(vwgts, num_vertices), num_vertices);
+        let weights = to_f32(as_slice(hewgts, num_nets), num_nets);
+        let h = Hypergraph::from_parts(capacities, weights, eptr, eind).map_err(|_| invalid)?;
+
+        // hMETIS bounds every bisection, while `Config::epsilon` bounds the
+        // final blocks, which compound the imbalance of each level.
+        let depth = usize::BITS - (k - 1).leading_zeros();
+        let mut config = Config {
+            epsilon: (1.0 + ubfactor as f32 / 50.0).powi(depth as i32) - 1.0,
+            ..Config::default()
+        };
+        if let Some(options) = as_slice(options, 9).filter(|options| options[0] != 0) {
+            config.repetitions = options[1].max(1) as usize;
+            config.seed = u64::try_from(options[7]).ok();
+        }
+        let result = partition(&h, k, &config);
+        for (p, block) in slice::from_raw_parts_mut(part, num_vertices)
+            .iter_mut()
+            .zip(result.blocks)
+        {
+            *p = block as c_int;
+        }
+        *edgecut = result.cut.round() as c_int;
+        Ok(())
+    });
+    if status != HpartStatus::Ok && !edgecut.is_null() {
+        *edgecut = -1;
+    }
+}
+
+/// Runs `f`, turning a panic into `HpartStatus::Panic`.
+fn guard(f: impl FnOnce() -> Result<(), HpartStatus>) -> HpartStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => HpartStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => HpartStatus::Panic,
+    }
+}
+
+/// `len` elements at `data`, or `None` if `data` is null. Null is fine for
+/// no elements.
+unsafe fn as_slice<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if data.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    Some(slice::from_raw_parts(data, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn c_api() {
+        // Two triangles joined by a light net.
+        let eptr = [0, 3, 6, 8];
+        let eind = [0, 1, 2, 3, 4, 5, 2, 3];
+        let weights = [5.0, 5.0, 1.0];
+        let mut h = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                hpart_hypergraph_new(
+                    6,
+                    3,
+                    eptr.as_ptr(),
+                    eind.as_ptr(),
+                    ptr::null(),
+                    weights.as_ptr(),
+                    &mut h
+                ),
+                HpartStatus::Ok
+            );
+            let mut blocks = [0; 6];
+            let mut cut = 0.0;
+            assert_eq!(
+                hpart_partition(h, 2, 0.0, 1, blocks.as_mut_ptr(), &mut cut, ptr::null_mut()),
+                HpartStatus::Ok
+            );
+            assert_eq!(cut, 1.0);
+            assert_ne!(blocks[0], blocks[5]);
+            assert_eq!(
+                hpart_partition(h, 0, 0.0, 1, blocks.as_mut_ptr(), &mut cut, ptr::null_mut()),
+                HpartStatus::InvalidArgument
+            );
+            hpart_hypergraph_free(h);
+
+            let eind = [0, 7];
+            let status = hpart_hypergraph_new(
+                2,
+                1,
+                [0, 2].as_ptr(),
+                eind.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                &mut h,
+            );
+            assert_eq!(status, HpartStatus::InvalidArgument);
+            let status = hpart_hypergraph_new(
+                0,
+                usize::MAX,
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                &mut h,
+            );
+            assert_eq!(status, HpartStatus::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn hmetis_part_recursive() {
+        let eptr = [0, 3, 6, 8];
+        let eind = [0, 1, 2, 3, 4, 5, 2, 3];
+        let hewgts = [5, 5, 1];
+        let options = [1, 1, 1, 1, 1, 0, 0, 3, 0];
+        let mut part = [0; 6];
+        let mut edgecut = 0;
+        unsafe {
+            HMETIS_PartRecursive(
+                6,
+                3,
+                ptr::null(),
+                eptr.as_ptr(),
+                eind.as_ptr(),
+                hewgts.as_ptr(),
+                2,
+                5,
+                options.as_ptr(),
+                part.as_mut_ptr(),
+                &mut edgecut,
+            );
+            assert_eq!(edgecut, 1);
+            assert_eq!(part[0], part[1]);
+            assert_ne!(part[0], part[5]);
+
+            HMETIS_PartRecursive(
+                6,
+                3,
+                ptr::null(),
+                eptr.as_ptr(),
+                eind.as_ptr(),
+                hewgts.as_ptr(),
+                2,
+                0,
+                ptr::null(),
+                part.as_mut_ptr(),
+                &mut edgecut,
+            );
+            assert_eq!(edgecut, -1);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use petgraph::visit::{Data, EdgeRef, IntoEdgeReferences, IntoNodeReferences, NodeRef};
+
+use crate::bipartite::Index;
+use crate::config::Config;
+use crate::hypergraph::Hypergraph;
+use crate::kway::{partition, PartitionResult};
+
+/// Partitions the nodes of a petgraph graph, such as a `Graph` or a
+/// `StableGraph`, into `k` blocks. Every edge becomes a 2-pin net, so the cut
+/// is the total weight of the edges between blocks. Edge directions are
+/// ignored and self-loops are skipped. Nodes have the capacity given by
+/// `node_weight` and edges the weight given by `edge_weight`. Returns the
+/// block of every node, along with the result on the hypergraph, whose
+/// vertices are the nodes in the order of `node_references`.
+///
+/// # Panics
+///
+/// Panics if `k` is zero.
+pub fn partition_graph<G>(
+    graph: G,
+    k: usize,
+    node_weight: impl Fn(&G::NodeWeight) -> f32,
+    edge_weight: impl Fn(&G::EdgeWeight) -> f32,
+    config: &Config,
+) -> (HashMap<G::NodeId, u32>, PartitionResult)
+where
+    G: Data + IntoNodeReferences + IntoEdgeReferences,
+    G::NodeId: Hash + Eq,
+{
+    let mut nodes = vec![];
+    let mut capacities = vec![];
+    for node in graph.node_references() {
+        nodes.push(node.id());
+        capacities.push(node_weight(node.weight()));
+    }
+    let ids: HashMap<G::NodeId, Index> = nodes
+        .iter()
+        .enumerate()
+        .map(|(v, node)| (*node, v as Index))
+        .collect();
+
+    let mut h = Hypergraph::new(capacities);
+    for edge in graph.edge_references() {
+        let (u, v) = (ids[&edge.source()], ids[&edge.target()]);
+        if u != v {
+            h.add_net(edge_weight(edge.weight()), &[u, v]);
+        }
+    }
+    let result = partition(&h, k, config);
+    let blocks = nodes
+        .into_iter()
+        .zip(result.blocks.iter().copied())
+        .collect();
+    (blocks, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::UnGraph;
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::*;
+
+    #[test]
+    fn two_cliques() {
+        // Two triangles of heavy edges, joined by a light edge.
+        let mut graph = UnGraph::<u32, f32>::new_undirected();
+        let nodes: Vec<_> = (0..6).map(|_| graph.add_node(1)).collect();
+        for (u, v) in [(0, 1), (1, 2), (0, 2), (3, 4), (4, 5), (3, 5)] {
+            graph.add_edge(nodes[u], nodes[v], 5.0);
+        }
+        graph.add_edge(nodes[2], nodes[3], 1.0);
+        let config = Config {
+            epsilon: 0.0,
+            seed: Some(0),
+            ..Config::default()
+        };
+        let (blocks, result) = partition_graph(&graph, 2, |w| *w as f32, |w| *w, &config);
+        assert_eq!(result.cut, 1.0);
+        assert_eq!(blocks[&nodes[0]], blocks[&nodes[2]]);
+        assert_ne!(blocks[&nodes[2]], blocks[&nodes[3]]);
+
+        // Removed nodes leave holes in the indices of a `StableGraph`.
+        let mut graph = StableDiGraph::<(), ()>::new();
+        let nodes: Vec<_> = (0..5).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[3], nodes[4], ());
+        graph.add_edge(nodes[4], nodes[4], ());
+        graph.remove_node(nodes[2]);
+        let (blocks, result) = partition_graph(&graph, 2, |_| 1.0, |_| 1.0, &config);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(result.blocks.len(), 4);
+        assert!(!blocks.contains_key(&nodes[2]));
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+
+use crate::bipartite::Index;
+
+/// A hypergraph as read from a file or assembled by the user. Vertices have
+/// capacities and nets have weights. The pins of all nets are stored back to
+/// back, as in the `eptr`/`eind` arrays of hMETIS: the pins of net `e` are
+/// `eind[eptr[e]..eptr[e + 1]]`. Vertices and nets can also have names, see
+/// `HypergraphBuilder`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "HypergraphParts")
+)]
+pub struct Hypergraph {
+    capacities: Vec<f32>,
+    weights: Vec<f32>,
+    eptr: Vec<usize>,
+    eind: Vec<Index>,
+    vertex_names: Option<Names>,
+    net_names: Option<Names>,
+}
+
+impl Hypergraph {
+    /// Creates a hypergraph with the given vertex capacities and no nets.
+    pub fn new(capacities: Vec<f32>) -> Self {
+        Hypergraph {
+            capacities,
+            weights: vec![],
+            eptr: vec![0],
+            eind: vec![],
+            vertex_names: None,
+            net_names: None,
+        }
+    }
+
+    /// Assembles a hypergraph from its arrays, as laid out in `Hypergraph`.
+    /// Fails if the arrays are inconsistent.
+    pub(crate) fn from_parts(
+        capacities: Vec<f32>,
+        weights: Vec<f32>,
+        eptr: Vec<usize>,
+        eind: Vec<Index>,
+    ) -> Result<Self, &'static str> {
+        if eptr.len() != weights.len() + 1 {
+            return Err("expected one more net pointer than nets");
+        }
+        if eptr.first() != Some(&0)
+            || eptr.last() != Some(&eind.len())
+            || eptr.windows(2).any(|w| w[0] > w[1])
+        {
+            return Err("net pointers must ascend from 0 to the number of pins");
+        }
+        if eind.iter().any(|v| *v as usize >= capacities.len()) {
+            return Err("pin out of range");
+        }
+        Ok(Hypergraph {
+            capacities,
+            weights,
+            eptr,
+            eind,
+            vertex_names: None,
+            net_names: None,
+        })
+    }
+
+    /// Adds a net and returns its ID. If the nets are named, the new net is
+    /// called `net<ID>`, as in `HypergraphBuilder::build`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pin is not a vertex of the hypergraph.
+    pub fn add_net(&mut self, weight: f32, pins: &[Index]) -> Index {
+        assert!(
+            pins.iter().all(|v| (*v as usize) < self.num_vertices()),
+            "pin out of range"
+        );
+        self.weights.push(weight);
+        self.eind.extend_from_slice(pins);
+        self.eptr.push(self.eind.len());
+        let e = (self.weights.len() - 1) as Index;
+        if let Some(names) = &mut self.net_names {
+            names.push(format!("net{e}"));
+        }
+        e
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.capacities.len()
+    }
+
+    pub fn num_nets(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn num_pins(&self) -> usize {
+        self.eind.len()
+    }
+
+    pub fn capacities(&self) -> &[f32] {
+        &self.capacities
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn eptr(&self) -> &[usize] {
+        &self.eptr
+    }
+
+    pub fn eind(&self) -> &[Index] {
+        &self.eind
+    }
+
+    /// The pins of net `e`.
+    pub fn net(&self, e: Index) -> &[Index] {
+        &self.eind[self.eptr[e as usize]..self.eptr[e as usize + 1]]
+    }
+
+    pub fn nets(&self) -> impl ExactSizeIterator<Item = &[Index]> + '_ {
+        (0..self.num_nets()).map(|e| self.net(e as Index))
+    }
+
+    /// Names the vertices, in order. Names needn't be unique, but lookups by
+    /// name only find the first vertex with a name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't exactly one name per vertex.
+    pub fn set_vertex_names(&mut self, names: Vec<String>) {
+        assert_eq!(names.len(), self.num_vertices(), "one name per vertex");
+        self.vertex_names = Some(Names::new(names));
+    }
+
+    /// Names the nets, in order. Nets added afterwards are named by
+    /// `add_net`. Names needn't be unique, but lookups by name only find the
+    /// first net with a name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't exactly one name per net.
+    pub fn set_net_names(&mut self, names: Vec<String>) {
+        assert_eq!(names.len(), self.num_nets(), "one name per net");
+        self.net_names = Some(Names::new(names));
+    }
+
+    /// The vertex names, if the vertices are named.
+    pub fn vertex_names(&self) -> Option<&[String]> {
+        self.vertex_names.as_ref().map(|names| &names.names[..])
+    }
+
+    /// The net names, if the nets are named.
+    pub fn net_names(&self) -> Option<&[String]> {
+        self.net_names.as_ref().map(|names| &names.names[..])
+    }
+
+    pub fn vertex_name(&self, v: Index) -> Option<&str> {
+        self.vertex_names.as_ref()?.name(v)
+    }
+
+    pub fn net_name(&self, e: Index) -> Option<&str> {
+        self.net_names.as_ref()?.name(e)
+    }
+
+    /// The ID of the vertex called `name`.
+    pub fn vertex_id(&self, name: &str) -> Option<Index> {
+        self.vertex_names.as_ref()?.id(name)
+    }
+
+    /// The ID of the net called `name`.
+    pub fn net_id(&self, name: &str) -> Option<Index> {
+        self.net_names.as_ref()?.id(name)
+    }
+
+    /// Total weight of the nets with pins in more than one block.
+    pub fn cut(&self, blocks: &[u32]) -> f32 {
+        zip_weights(self, blocks)
+            .filter(|(_, connectivity)| *connectivity > 1)
+            .map(|(w, _)| w)
+            .fold(0.0, |total, w| total + w)
+    }
+
+    /// Sum over all nets of the weight times the number of blocks beyond the
+    /// first that the net spans, known as the connectivity metric.
+    pub fn km1(&self, blocks: &[u32]) -> f32 {
+        zip_weights(self, blocks)
+            .map(|(w, connectivity)| w * connectivity.saturating_sub(1) as f32)
+            .fold(0.0, |total, w| total + w)
+    }
+
+    /// Total capacity of each of the `k` blocks.
+    pub fn block_capacities(&self, blocks: &[u32], k: usize) -> Vec<f32> {
+        let mut capacities = vec![0.0; k];
+        for (c, block) in self.capacities.iter().zip(blocks) {
+            capacities[*block as usize] += c;
+        }
+        capacities
+    }
+
+    /// Number of distinct blocks the pins of net `e` are in.
+    pub fn connectivity(&self, e: Index, blocks: &[u32]) -> usize {
+        let mut seen: Vec<u32> = self.net(e).iter().map(|v| blocks[*v as usize]).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        seen.len()
+    }
+}
+
+fn zip_weights<'a>(
+    h: &'a Hypergraph,
+    blocks: &'a [u32],
+) -> impl Iterator<Item = (f32, usize)> + 'a {
+    (0..h.num_nets()).map(move |e| (h.weights[e], h.connectivity(e as Index, blocks)))
+}
+
+/// Builds a hypergraph whose vertices and nets are identified by name rather
+/// than by ID. IDs are assigned densely in order of first appearance.
+#[derive(Clone, Debug, Default)]
+pub struct HypergraphBuilder {
+    capacities: Vec<f32>,
+    weights: Vec<f32>,
+    eptr: Vec<usize>,
+    eind: Vec<Index>,
+    vertices: Names,
+    net_names: Vec<Option<String>>,
+}
+
+impl HypergraphBuilder {
+    pub fn new() -> Self {
+        HypergraphBuilder {
+            eptr: vec![0],
+            ..Default::default()
+        }
+    }
+
+    /// Adds a vertex called `name`, or sets its capacity if it exists.
+    /// Returns its ID.
+    pub fn vertex(&mut self, name: &str, capacity: f32) -> Index {
+        let v = self.vertices.get_or_insert(name);
+        match self.capacities.get_mut(v as usize) {
+            Some(c) => *c = capacity,
+            None => self.capacities.push(capacity),
+        }
+        v
+    }
+
+    /// Adds an unnamed net and returns its ID. Pins that aren't vertices yet
+    /// are added with unit capacity.
+    pub fn net(&mut self, weight: f32, pins: &[impl AsRef<str>]) -> Index {
+        for pin in pins {
+            let v = self.vertices.get_or_insert(pin.as_ref());
+            if v as usize == self.capacities.len() {
+                self.capacities.push(1.0);
+            }
+            self.eind.push(v);
+        }
+        self.eptr.push(self.eind.len());
+        self.weights.push(weight);
+        self.net_names.push(None);
+        (self.weights.len() - 1) as Index
+    }
+
+    /// Adds a net called `name`, see `net`.
+    pub fn named_net(&mut self, name: &str, weight: f32, pins: &[impl AsRef<str>]) -> Index {
+        let e = self.net(weight, pins);
+        self.net_names[e as usize] = Some(name.to_string());
+        e
+    }
+
+    /// Builds the hypergraph. Its vertices are named. If any net is named,
+    /// so are the rest, as `net<ID>`.
+    pub fn build(self) -> Hypergraph {
+        let net_names = self.net_names.iter().any(Option::is_some).then(|| {
+            let names = self.net_names.into_iter().enumerate();
+            Names::new(
+                names
+                    .map(|(e, name)| name.unwrap_or_else(|| format!("net{e}")))
+                    .collect(),
+            )
+        });
+        Hypergraph {
+            capacities: self.capacities,
+            weights: self.weights,
+            eptr: self.eptr,
+            eind: self.eind,
+            vertex_names: Some(self.vertices),
+            net_names,
+        }
+    }
+}
+
+/// Names of dense IDs, with a reverse index. Only the first of equal names
+/// is in the reverse index.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<String>", into = "Vec<String>")
+)]
+struct Names {
+    names: Vec<String>,
+    ids: HashMap<String, Index>,
+}
+
+impl Names {
+    fn new(names: Vec<String>) -> Self {
+        let mut ids = HashMap::with_capacity(names.len());
+        for (id, name) in names.iter().enumerate() {
+            ids.entry(name.clone()).or_insert(id as Index);
+        }
+        Names { names, ids }
+    }
+
+    fn name(&self, id: Index) -> Option<&str> {
+        self.names.get(id as usize).map(String::as_str)
+    }
+
+    fn id(&self, name: &str) -> Option<Index> {
+        self.ids.get(name).copied()
+    }
+
+    /// Names the next ID.
+    fn push(&mut self, name: String) {
+        let id = self.names.len() as Index;
+        self.ids.entry(name.clone()).or_insert(id);
+        self.names.push(name);
+    }
+
+    /// The ID of `name`, which is added with the next ID if it's new.
+    fn get_or_insert(&mut self, name: &str) -> Index {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len() as Index;
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+}
+
+impl From<Vec<String>> for Names {
+    fn from(names: Vec<String>) -> Self {
+        Names::new(names)
+    }
+}
+
+impl From<Names> for Vec<String> {
+    fn from(names: Names) -> Self {
+        names.names
+    }
+}
+
+/// The fields of a `Hypergraph` as deserialized, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct HypergraphParts {
+    capacities: Vec<f32>,
+    weights: Vec<f32>,
+    eptr: Vec<usize>,
+    eind: Vec<Index>,
+    vertex_names: Option<Names>,
+    net_names: Option<Names>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HypergraphParts> for Hypergraph {
+    type Error = &'static str;
+
+    fn try_from(parts: HypergraphParts) -> Result<Self, Self::Error> {
+        let mut h =
+            Hypergraph::from_parts(parts.capacities, parts.weights, parts.eptr, parts.eind)?;
+        if let Some(names) = parts.vertex_names {
+            if names.names.len() != h.num_vertices() {
+                return Err("expected one name per vertex");
+            }
+            h.vertex_names = Some(names);
+        }
+        if let Some(names) = parts.net_names {
+            if names.names.len() != h.num_nets() {
+                return Err("expected one name per net");
+            }
+            h.net_names = Some(names);
+        }
+        Ok(h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_names() {
+        let mut builder = HypergraphBuilder::new();
+        builder.vertex("a", 2.0);
+        builder.named_net("n0", 1.0, &["a", "b"]);
+        builder.net(3.0, &["b", "c"]);
+        builder.vertex("c", 4.0);
+        let h = builder.build();
+        assert_eq!(h.capacities(), [2.0, 1.0, 4.0]);
+        assert_eq!(h.nets().collect::<Vec<_>>(), [[0, 1], [1, 2]]);
+        assert_eq!(h.vertex_id("c"), Some(2));
+        assert_eq!(h.vertex_name(1), Some("b"));
+        assert_eq!(h.net_id("net1"), Some(1));
+        assert_eq!(h.net_names().unwrap(), ["n0", "net1"]);
+        assert_eq!(h.vertex_id("d"), None);
+
+        let mut builder = HypergraphBuilder::new();
+        builder.net(1.0, &["a"]);
+        assert_eq!(builder.build().net_names(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json() {
+        let mut builder = HypergraphBuilder::new();
+        builder.named_net("n0", 2.0, &["a", "b", "c"]);
+        let mut h = builder.build();
+        h.add_net(1.0, &[0, 2]);
+        assert_eq!(h.net_names().unwrap(), ["n0", "net1"]);
+        let json = serde_json::to_string(&h).unwrap();
+        assert_eq!(serde_json::from_str::<Hypergraph>(&json).unwrap(), h);
+
+        let json = r#"{"capacities":[1.0],"weights":[1.0],"eptr":[0,1],"eind":[1],
+            "vertex_names":null,"net_names":null}"#;
+        assert!(serde_json::from_str::<Hypergraph>(json).is_err());
+    }
+}
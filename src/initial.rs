@@ -1,28 +1,260 @@
 use std::collections::VecDeque;
-use std::mem::replace;
+use std::fmt::Debug;
+use std::sync::Arc;
 
 use bitvec::prelude::*;
 use ordered_float::OrderedFloat;
 use rand::prelude::*;
+use rayon::prelude::*;
 
 use crate::bipartite::*;
+use crate::budget::Budget;
+use crate::config::Config;
+use crate::uncoarsen::refine;
+
+mod exact;
+mod ghg;
+mod spectral;
+
+use exact::exact_bipartition;
+use ghg::greedy_hypergraph_growing;
+use spectral::spectral;
+
+pub use ghg::{GhgGain, GhgGrowth};
+
+/// An algorithm for bipartitioning the coarsest hypergraph. Every algorithm in
+/// the portfolio is run a configured number of times, each candidate is
+/// refined, and the best one is kept.
+pub trait InitialPartitioner: Debug + Send + Sync {
+    /// Returns a bipartition of `h`, indexed by vertex ID, with
+    /// `h.vertex_id_bound()` entries. Each block should respect
+    /// `h.size_constraint(epsilon)`, but candidates that don't are only chosen
+    /// if nothing better is found. The time limit is only checked between
+    /// candidates, so algorithms that take long should poll `h.exhausted()`
+    /// and return early once it's true.
+    fn partition(&self, h: CoarseHypergraph, epsilon: f32, rng: &mut dyn RngCore) -> Bipartition;
+}
+
+/// Read-only view of the coarsest hypergraph, as handed to an
+/// `InitialPartitioner`. Coarsening leaves gaps in the vertex IDs: they range
+/// over `0..vertex_id_bound()`, but only `vertices()` are part of the
+/// hypergraph. Net IDs have gaps too, where coarsening removed redundant
+/// nets.
+#[derive(Clone, Copy, Debug)]
+pub struct CoarseHypergraph<'a>(&'a Bipartite, &'a Budget);
+
+impl<'a> CoarseHypergraph<'a> {
+    /// The IDs of the vertices.
+    pub fn vertices(&self) -> impl Iterator<Item = Index> + Clone + 'a {
+        self.0.pins()
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.0.num_pins()
+    }
+
+    /// One past the largest vertex ID, i.e. the length of a bipartition.
+    pub fn vertex_id_bound(&self) -> usize {
+        self.0.pin_index_space_size()
+    }
+
+    /// The IDs of the nets.
+    pub fn nets(&self) -> impl Iterator<Item = Index> + Clone + 'a {
+        self.0.nets()
+    }
+
+    pub fn num_nets(&self) -> usize {
+        self.0.num_nets()
+    }
+
+    /// One past the largest net ID.
+    pub fn net_id_bound(&self) -> usize {
+        self.0.net_index_space_size()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `v` is not one of `vertices()`.
+    pub fn capacity(&self, v: Index) -> f32 {
+        self.0.capacity(v)
+    }
+
+    pub fn weight(&self, e: Index) -> f32 {
+        self.0.weight(e)
+    }
+
+    /// The pins of net `e`.
+    pub fn pins(&self, e: Index) -> impl ExactSizeIterator<Item = Index> + Clone + 'a {
+        self.0.pins_in_net(e)
+    }
+
+    /// The nets that `v` is a pin of.
+    pub fn incident_nets(&self, v: Index) -> impl ExactSizeIterator<Item = Index> + Clone + 'a {
+        self.0.incident_nets(v)
+    }
+
+    /// The vertices sharing a net with `v`. A vertex may appear more than once.
+    pub fn neighbors(&self, v: Index) -> impl Iterator<Item = Index> + Clone + 'a {
+        self.0.incident_pins(v)
+    }
+
+    /// The summed capacity of all vertices.
+    pub fn total_capacity(&self) -> f32 {
+        self.0.total_capacity()
+    }
+
+    /// The largest capacity a block may have under `epsilon`.
+    pub fn size_constraint(&self, epsilon: f32) -> f32 {
+        self.0.size_constraint(epsilon)
+    }
+
+    /// Returns the capacity of the heavier block and the weight of the cut
+    /// nets.
+    pub fn evaluate_bipartition(&self, p: &Bipartition) -> (f32, f32) {
+        self.0.evaluate_bipartition(p)
+    }
+
+    /// Whether the run is out of time or has been cancelled. Once this
+    /// returns true, it keeps doing so.
+    pub fn exhausted(&self) -> bool {
+        self.1.exhausted()
+    }
+}
+
+/// Assigns every vertex to a block uniformly at random.
+#[derive(Clone, Copy, Debug)]
+pub struct RandomPartitioner;
+
+impl InitialPartitioner for RandomPartitioner {
+    fn partition(
+        &self,
+        h: CoarseHypergraph,
+        _epsilon: f32,
+        mut rng: &mut dyn RngCore,
+    ) -> Bipartition {
+        random_partitioning(h.0, &mut rng)
+    }
+}
+
+/// Grows one block by capacity-aware breadth-first search.
+#[derive(Clone, Copy, Debug)]
+pub struct BfsPartitioner;
+
+impl InitialPartitioner for BfsPartitioner {
+    fn partition(
+        &self,
+        h: CoarseHypergraph,
+        _epsilon: f32,
+        mut rng: &mut dyn RngCore,
+    ) -> Bipartition {
+        bfs_half(h.0, &mut rng)
+    }
+}
+
+/// Size-constrained label propagation from a pair of pseudo-peripheral seeds.
+#[derive(Clone, Copy, Debug)]
+pub struct LabelPropagationPartitioner;
+
+impl InitialPartitioner for LabelPropagationPartitioner {
+    fn partition(
+        &self,
+        h: CoarseHypergraph,
+        epsilon: f32,
+        mut rng: &mut dyn RngCore,
+    ) -> Bipartition {
+        size_constrained_label_propagation(h.0, epsilon, &mut rng)
+    }
+}
+
+/// Greedy hypergraph growing.
+#[derive(Clone, Copy, Debug)]
+pub struct GhgPartitioner {
+    pub gain: GhgGain,
+    pub growth: GhgGrowth,
+}
+
+impl InitialPartitioner for GhgPartitioner {
+    fn partition(
+        &self,
+        h: CoarseHypergraph,
+        epsilon: f32,
+        mut rng: &mut dyn RngCore,
+    ) -> Bipartition {
+        greedy_hypergraph_growing(h.0, epsilon, self.gain, self.growth, &mut rng)
+    }
+}
+
+/// Splits the Fiedler vector of the clique expansion at the lowest cut that
+/// respects the size constraint.
+#[derive(Clone, Copy, Debug)]
+pub struct SpectralPartitioner;
+
+impl InitialPartitioner for SpectralPartitioner {
+    fn partition(
+        &self,
+        h: CoarseHypergraph,
+        epsilon: f32,
+        mut rng: &mut dyn RngCore,
+    ) -> Bipartition {
+        spectral(h.0, epsilon, &mut rng)
+    }
+}
 
 const NUM_ITERS: usize = 20;
+// Spectral runs mostly converge to the same vector, so fewer are needed.
+const NUM_SPECTRAL_ITERS: usize = 5;
 
-/// Compute an initial partitioning on a coarsened hypergraph. Runs a portfolio
-/// of algorithms and returns the best found bipartition.
-pub(crate) fn initial_partitioning(h: &Bipartite, epsilon: f32) -> Bipartition {
-    let random_parts = (0..NUM_ITERS).map(|_| random_partitioning(h));
-    let bfs_half_parts = (0..NUM_ITERS).map(|_| bfs_half(h));
-    let sclap_parts = (0..NUM_ITERS).map(|_| size_constrained_label_propagation(h, epsilon));
-    let all_partitions: Vec<_> = random_parts
-        .chain(bfs_half_parts)
-        .chain(sclap_parts)
-        .collect();
+/// The built-in algorithms, each with its number of runs.
+pub(crate) fn default_portfolio() -> Vec<(Arc<dyn InitialPartitioner>, usize)> {
+    let mut portfolio: Vec<(Arc<dyn InitialPartitioner>, usize)> = vec![
+        (Arc::new(RandomPartitioner), NUM_ITERS),
+        (Arc::new(BfsPartitioner), NUM_ITERS),
+        (Arc::new(LabelPropagationPartitioner), NUM_ITERS),
+    ];
+    for gain in [GhgGain::Fm, GhgGain::MaxNet, GhgGain::MaxPin] {
+        for growth in [GhgGrowth::Global, GhgGrowth::RoundRobin] {
+            portfolio.push((Arc::new(GhgPartitioner { gain, growth }), NUM_ITERS));
+        }
+    }
+    portfolio.push((Arc::new(SpectralPartitioner), NUM_SPECTRAL_ITERS));
+    portfolio
+}
 
-    let size_constraint = h.size_constraint(epsilon);
-    all_partitions
-        .into_iter()
+/// Compute an initial partitioning on a coarsened hypergraph. Runs the
+/// portfolio of algorithms in parallel, refines every candidate with FM, and
+/// returns the best refined bipartition. Small enough hypergraphs are then
+/// handed to the exact partitioner, if enabled, with that bipartition as the
+/// upper bound. Once the budget is exhausted, runs that haven't started yet
+/// are skipped, except for the first, which is always needed.
+pub(crate) fn initial_partitioning(
+    h: &Bipartite,
+    config: &Config,
+    budget: &Budget,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    // Every run gets its own seed, so the result doesn't depend on how runs
+    // are scheduled across threads.
+    let mut runs = vec![];
+    for (partitioner, num_runs) in config.portfolio.iter() {
+        runs.extend((0..*num_runs).map(|_| (&**partitioner, rng.gen::<u64>())));
+    }
+
+    let size_constraint = h.size_constraint(config.epsilon);
+    let best = runs
+        .into_par_iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx == 0 || !budget.exhausted())
+        .map(|(_, (partitioner, seed))| {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            let mut part = partitioner.partition(CoarseHypergraph(h, budget), config.epsilon, rng);
+            assert_eq!(
+                part.len(),
+                h.pin_index_space_size(),
+                "{partitioner:?} returned a bipartition of the wrong length"
+            );
+            refine(h, size_constraint, &mut part, budget);
+            part
+        })
         .min_by_key(|part| {
             let (imbalance, weight) = h.evaluate_bipartition(part);
             (
@@ -30,35 +262,53 @@ pub(crate) fn initial_partitioning(h: &Bipartite, epsilon: f32) -> Bipartition {
                 OrderedFloat(weight),
             )
         })
-        .unwrap()
+        .expect("the initial partitioning portfolio is empty");
+
+    match config.exact {
+        Some(exact) if h.num_pins() <= exact.max_vertices && !budget.exhausted() => {
+            exact_bipartition(h, size_constraint, best, exact.time_limit, budget)
+        }
+        _ => best,
+    }
 }
 
-fn random_partitioning(h: &Bipartite) -> Bipartition {
-    (0..h.pin_index_space_size()).map(|_| random()).collect()
+fn random_partitioning(h: &Bipartite, rng: &mut impl Rng) -> Bipartition {
+    (0..h.pin_index_space_size()).map(|_| rng.gen()).collect()
 }
 
-fn bfs_half(h: &Bipartite) -> Bipartition {
-    let start_v = random_vertex(h);
-    let mut queue = VecDeque::new();
-    queue.push_back(start_v);
+/// Grows one block by breadth-first search, up to half of the total capacity.
+/// A vertex is only added if that brings the block closer to its target, so
+/// heavy vertices are left for the other block. When a component is
+/// exhausted, the search restarts from a random unvisited vertex.
+fn bfs_half(h: &Bipartite, rng: &mut impl Rng) -> Bipartition {
+    let target = h.total_capacity() / 2.0;
+    let mut bipartition = vec![false; h.pin_index_space_size()];
     let mut visited = bitvec![usize, Lsb0; 0; h.pin_index_space_size()];
-    visited.set(start_v as usize, true);
+    let mut order: Vec<_> = h.pins().collect();
+    order.shuffle(rng);
 
-    let mut bipartition = vec![false; h.pin_index_space_size()];
-    let mut num_visited = 0;
-    while let Some(pop) = queue.pop_front() {
-        for neighbor in h.incident_pins(pop) {
+    let mut queue = VecDeque::new();
+    let mut seeds = order.into_iter();
+    let mut capacity = 0.0;
+    while capacity < target {
+        let Some(v) = queue
+            .pop_front()
+            .or_else(|| seeds.find(|v| !visited[*v as usize]))
+        else {
+            break;
+        };
+        visited.set(v as usize, true);
+        for neighbor in h.incident_pins(v) {
             if !visited[neighbor as usize] {
                 visited.set(neighbor as usize, true);
                 queue.push_back(neighbor);
             }
         }
 
-        if num_visited < h.num_pins() / 2 {
-            bipartition[pop as usize] = true;
-            num_visited += 1;
-        } else {
-            break;
+        let c = h.capacity(v);
+        if capacity + c / 2.0 < target {
+            bipartition[v as usize] = true;
+            capacity += c;
         }
     }
 
@@ -67,8 +317,12 @@ fn bfs_half(h: &Bipartite) -> Bipartition {
 
 const TAU: usize = 5;
 
-fn size_constrained_label_propagation(h: &Bipartite, epsilon: f32) -> Bipartition {
-    let (v1, v2) = pseudo_peripheral_vertices(h);
+fn size_constrained_label_propagation(
+    h: &Bipartite,
+    epsilon: f32,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let (v1, v2) = pseudo_peripheral_vertices(h, rng);
     let mut labels = vec![None; h.pin_index_space_size()];
     let mut c1 = 0.0;
     let mut c2 = 0.0;
@@ -79,7 +333,7 @@ fn size_constrained_label_propagation(h: &Bipartite, epsilon: f32) -> Bipartitio
                   c1: &mut f32,
                   c2: &mut f32,
                   n: &mut usize| {
-        let old = replace(&mut labels[v as usize], Some(l));
+        let old = labels[v as usize].replace(l);
         let c = h.capacity(v);
         match (old, l) {
             (Some(false), true) => {
@@ -106,9 +360,8 @@ fn size_constrained_label_propagation(h: &Bipartite, epsilon: f32) -> Bipartitio
 
     let mut n1: Vec<_> = h.incident_pins(v1).collect();
     let mut n2: Vec<_> = h.incident_pins(v2).collect();
-    let mut rng = thread_rng();
-    n1.shuffle(&mut rng);
-    n2.shuffle(&mut rng);
+    n1.shuffle(rng);
+    n2.shuffle(rng);
     n1.truncate(TAU);
     n2.truncate(TAU);
     for v in n1 {
@@ -150,7 +403,7 @@ fn size_constrained_label_propagation(h: &Bipartite, epsilon: f32) -> Bipartitio
     labels.into_iter().map(|l| l.unwrap_or(false)).collect()
 }
 
-fn pseudo_peripheral_vertices(h: &Bipartite) -> (Index, Index) {
+fn pseudo_peripheral_vertices(h: &Bipartite, rng: &mut impl Rng) -> (Index, Index) {
     let last_bfs = |start_v| {
         let mut queue = VecDeque::new();
         queue.push_back(start_v);
@@ -170,17 +423,81 @@ fn pseudo_peripheral_vertices(h: &Bipartite) -> (Index, Index) {
         last
     };
 
-    let v1 = random_vertex(h);
+    let v1 = random_vertex(h, rng);
     let v2 = last_bfs(v1);
     let v3 = last_bfs(v2);
     (v2, v3)
 }
 
-fn random_vertex(h: &Bipartite) -> Index {
+fn random_vertex(h: &Bipartite, rng: &mut impl Rng) -> Index {
     loop {
-        let v: Index = random::<Index>() % h.pin_index_space_size() as Index;
+        let v: Index = rng.gen::<Index>() % h.pin_index_space_size() as Index;
         if h.enabled(v) {
             return v;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initial_partitioning_keeps_best_candidate() {
+        // A 6x6 grid, with a few nets spanning whole rows.
+        let mut nets = vec![];
+        for v in 0..36 {
+            if v % 6 < 5 {
+                nets.push(vec![v, v + 1]);
+            }
+            if v < 30 {
+                nets.push(vec![v, v + 6]);
+            }
+        }
+        nets.extend([0, 2, 5].map(|row| (row * 6..row * 6 + 6).collect()));
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let h = Bipartite::new(&[1.0; 36], &vec![1.0; nets.len()], &nets);
+        let config = Config::default();
+        let budget = Budget::new(&config);
+
+        let best = initial_partitioning(&h, &config, &budget, &mut StdRng::seed_from_u64(7));
+        let again = initial_partitioning(&h, &config, &budget, &mut StdRng::seed_from_u64(7));
+        assert_eq!(best, again);
+
+        // Rerun every candidate with the seed it was given.
+        let size_constraint = h.size_constraint(config.epsilon);
+        let key = |part: &Bipartition| {
+            let (imbalance, cut) = h.evaluate_bipartition(part);
+            (
+                OrderedFloat(imbalance.max(size_constraint)),
+                OrderedFloat(cut),
+            )
+        };
+        let rng = &mut StdRng::seed_from_u64(7);
+        for (partitioner, num_runs) in config.portfolio.iter() {
+            for _ in 0..*num_runs {
+                let rng = &mut StdRng::seed_from_u64(rng.gen());
+                let mut part =
+                    partitioner.partition(CoarseHypergraph(&h, &budget), config.epsilon, rng);
+                refine(&h, size_constraint, &mut part, &budget);
+                assert!(key(&best) <= key(&part), "{partitioner:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn bfs_half_balanced() {
+        // A path of 30 unit vertices, with a single heavy vertex in the middle.
+        let mut capacities = vec![1.0; 30];
+        capacities[15] = 10.0;
+        let nets: Vec<_> = (0..29).map(|v| [v, v + 1]).collect();
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let h = Bipartite::new(&capacities, &[1.0; 29], &nets);
+
+        for seed in 0..20 {
+            let bipartition = bfs_half(&h, &mut StdRng::seed_from_u64(seed));
+            let (imbalance, _) = h.evaluate_bipartition(&bipartition);
+            assert!(imbalance <= 24.0, "{imbalance}");
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bitvec::prelude::*;
+
+use crate::bipartite::*;
+use crate::budget::Budget;
+
+/// Number of search nodes between checks of the time limit and the budget.
+const CHECK_INTERVAL: usize = 1024;
+
+/// Branch-and-bound search for a minimum cut bipartition within the size
+/// constraint. Vertices are assigned in BFS order, so nets are completed early
+/// and the cut of the partial assignment, which is the lower bound, rises
+/// quickly. `incumbent` is the starting upper bound. Returns the optimal
+/// bipartition, or the best one found once `time_limit` or `budget` runs out.
+pub(crate) fn exact_bipartition(
+    h: &Bipartite,
+    size_constraint: f32,
+    incumbent: Bipartition,
+    time_limit: Duration,
+    budget: &Budget,
+) -> Bipartition {
+    let (imbalance, cut) = h.evaluate_bipartition(&incumbent);
+    let mut search = Search {
+        h,
+        size_constraint,
+        order: bfs_order(h),
+        counts: vec![[0; 2]; h.net_index_space_size()],
+        labels: vec![false; h.pin_index_space_size()],
+        capacities: [0.0; 2],
+        cut: 0.0,
+        best_cut: if imbalance <= size_constraint {
+            cut
+        } else {
+            f32::INFINITY
+        },
+        best: incumbent,
+        deadline: Instant::now() + time_limit,
+        budget,
+        nodes: 0,
+        timed_out: false,
+    };
+    search.search(0);
+    search.best
+}
+
+struct Search<'a> {
+    h: &'a Bipartite,
+    size_constraint: f32,
+    order: Vec<Index>,
+    // Number of assigned pins of each net in each block.
+    counts: Vec<[u32; 2]>,
+    labels: Bipartition,
+    capacities: [f32; 2],
+    cut: f32,
+    best_cut: f32,
+    best: Bipartition,
+    deadline: Instant,
+    budget: &'a Budget,
+    nodes: usize,
+    timed_out: bool,
+}
+
+impl Search<'_> {
+    fn search(&mut self, depth: usize) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL)
+            && (Instant::now() >= self.deadline || self.budget.exhausted())
+        {
+            self.timed_out = true;
+        }
+        if self.timed_out {
+            return;
+        }
+        if depth == self.order.len() {
+            if self.cut < self.best_cut {
+                self.best_cut = self.cut;
+                self.best = self.labels.clone();
+            }
+            return;
+        }
+
+        // Fixing the first vertex breaks the symmetry between the blocks. After
+        // that, the block that cuts fewer new nets is tried first.
+        let v = self.order[depth];
+        let mut blocks = [true, false];
+        if self.delta(v, false) < self.delta(v, true) {
+            blocks.reverse();
+        }
+        let num_blocks = if depth == 0 { 1 } else { 2 };
+        for b in &blocks[..num_blocks] {
+            if self.capacities[*b as usize] + self.h.capacity(v) > self.size_constraint {
+                continue;
+            }
+            self.assign(v, *b, true);
+            if self.cut < self.best_cut {
+                self.search(depth + 1);
+            }
+            self.assign(v, *b, false);
+        }
+    }
+
+    /// Weight of the nets that would become cut by assigning `v` to `b`.
+    fn delta(&self, v: Index, b: bool) -> f32 {
+        self.h
+            .incident_nets(v)
+            .filter(|e| {
+                let counts = self.counts[*e as usize];
+                counts[b as usize] == 0 && counts[!b as usize] > 0
+            })
+            .map(|e| self.h.weight(e))
+            .sum()
+    }
+
+    /// Assigns `v` to `b`, or undoes that assignment.
+    fn assign(&mut self, v: Index, b: bool, assign: bool) {
+        let c = self.h.capacity(v);
+        if assign {
+            self.cut += self.delta(v, b);
+            self.capacities[b as usize] += c;
+            self.labels[v as usize] = b;
+        } else {
+            self.capacities[b as usize] -= c;
+        }
+        for e in self.h.incident_nets(v) {
+            let counts = &mut self.counts[e as usize];
+            if assign {
+                counts[b as usize] += 1;
+            } else {
+                counts[b as usize] -= 1;
+            }
+        }
+        if !assign {
+            self.cut -= self.delta(v, b);
+        }
+    }
+}
+
+/// Orders the vertices by BFS, starting each component from its vertex with
+/// the most incident nets.
+fn bfs_order(h: &Bipartite) -> Vec<Index> {
+    let mut starts: Vec<_> = h.pins().collect();
+    starts.sort_by_key(|v| std::cmp::Reverse(h.incident_nets(*v).len()));
+
+    let mut visited = bitvec![usize, Lsb0; 0; h.pin_index_space_size()];
+    let mut order = Vec::with_capacity(h.num_pins());
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if visited[start as usize] {
+            continue;
+        }
+        visited.set(start as usize, true);
+        queue.push_back(start);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            for neighbor in h.incident_pins(v) {
+                if !visited[neighbor as usize] {
+                    visited.set(neighbor as usize, true);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn exact_two_cliques() {
+        // Two cliques of 5 vertices, joined by a single light net.
+        let mut nets = vec![];
+        for offset in [0, 5] {
+            for u in 0..5 {
+                for v in u + 1..5 {
+                    nets.push(vec![offset + u, offset + v]);
+                }
+            }
+        }
+        nets.push(vec![4, 5]);
+        let mut weights = vec![1.0; nets.len()];
+        *weights.last_mut().unwrap() = 0.5;
+        let nets: Vec<&[_]> = nets.iter().map(|net| &**net).collect();
+        let h = Bipartite::new(&[1.0; 10], &weights, &nets);
+
+        let incumbent = (0..10).map(|v| v % 2 == 0).collect();
+        let size_constraint = h.size_constraint(0.0);
+        let budget = Budget::new(&Config::default());
+        let best = exact_bipartition(
+            &h,
+            size_constraint,
+            incumbent,
+            Duration::from_secs(10),
+            &budget,
+        );
+        assert_eq!(h.evaluate_bipartition(&best), (5.0, 0.5));
+    }
+}
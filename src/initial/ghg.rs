@@ -0,0 +1,210 @@
+use std::collections::BinaryHeap;
+
+use ordered_float::OrderedFloat;
+use rand::prelude::*;
+
+use super::pseudo_peripheral_vertices;
+use crate::bipartite::*;
+
+/// How the gain of adding a vertex to a growing block is rated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GhgGain {
+    /// The reduction in cut weight, counting unassigned vertices as part of
+    /// the other block.
+    Fm,
+    /// The weight of the vertex's nets that already have a pin in the block.
+    MaxNet,
+    /// The weight of the vertex's nets, counted once per pin already in the
+    /// block.
+    MaxPin,
+}
+
+/// Which block gets the next vertex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GhgGrowth {
+    /// The block with the best rated candidate.
+    Global,
+    /// The blocks take turns.
+    RoundRobin,
+}
+
+/// Greedy hypergraph growing, as in KaHyPar and PaToH. Both blocks grow from a
+/// pair of pseudo-peripheral seeds by repeatedly adding the best rated
+/// unassigned vertex on their frontier, until they hold half of the total
+/// capacity. A block whose frontier runs dry restarts from a random unassigned
+/// vertex.
+pub(crate) fn greedy_hypergraph_growing(
+    h: &Bipartite,
+    epsilon: f32,
+    gain: GhgGain,
+    growth: GhgGrowth,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let target = h.total_capacity() / 2.0;
+    let size_constraint = h.size_constraint(epsilon);
+    let mut grower = Grower {
+        h,
+        gain,
+        labels: vec![None; h.pin_index_space_size()],
+        capacities: [0.0; 2],
+        queues: [BinaryHeap::new(), BinaryHeap::new()],
+        gains: [
+            vec![0.0; h.pin_index_space_size()],
+            vec![0.0; h.pin_index_space_size()],
+        ],
+    };
+
+    let (v1, v2) = pseudo_peripheral_vertices(h, rng);
+    grower.assign(v1, true);
+    if v2 != v1 {
+        grower.assign(v2, false);
+    }
+
+    let mut order: Vec<_> = h.pins().collect();
+    order.shuffle(rng);
+    let mut next_seed = 0;
+    let mut turn = false;
+    loop {
+        let mut tops = [None, None];
+        for b in [false, true] {
+            if grower.capacities[b as usize] >= target {
+                continue;
+            }
+            tops[b as usize] = grower.top(b, size_constraint).or_else(|| {
+                while next_seed < order.len() && grower.labels[order[next_seed] as usize].is_some()
+                {
+                    next_seed += 1;
+                }
+                let v = *order.get(next_seed)?;
+                (grower.capacities[b as usize] + h.capacity(v) <= size_constraint)
+                    .then_some((f32::NEG_INFINITY, v))
+            });
+        }
+
+        let block = match (growth, tops) {
+            (_, [None, None]) => break,
+            (_, [Some(_), None]) => false,
+            (_, [None, Some(_)]) => true,
+            (GhgGrowth::Global, [Some((g1, _)), Some((g2, _))]) => g2 > g1,
+            (GhgGrowth::RoundRobin, _) => {
+                turn = !turn;
+                turn
+            }
+        };
+        let (_, v) = tops[block as usize].unwrap();
+        grower.assign(v, block);
+    }
+
+    // Whatever couldn't be grown into goes to the lighter block.
+    for v in order {
+        if grower.labels[v as usize].is_none() {
+            let block = grower.capacities[1] < grower.capacities[0];
+            grower.labels[v as usize] = Some(block);
+            grower.capacities[block as usize] += h.capacity(v);
+        }
+    }
+
+    grower
+        .labels
+        .into_iter()
+        .map(|l| l.unwrap_or(false))
+        .collect()
+}
+
+struct Grower<'a> {
+    h: &'a Bipartite,
+    gain: GhgGain,
+    labels: Vec<Option<bool>>,
+    capacities: [f32; 2],
+    // Lazy priority queues of candidates for each block. An entry is stale if
+    // its gain doesn't match `gains`, or if its vertex has been assigned.
+    queues: [BinaryHeap<(OrderedFloat<f32>, Index)>; 2],
+    gains: [Vec<f32>; 2],
+}
+
+impl Grower<'_> {
+    fn assign(&mut self, v: Index, b: bool) {
+        self.labels[v as usize] = Some(b);
+        self.capacities[b as usize] += self.h.capacity(v);
+        for u in self.h.incident_pins(v) {
+            if self.labels[u as usize].is_none() {
+                for b in [false, true] {
+                    let gain = self.rate(u, b);
+                    self.gains[b as usize][u as usize] = gain;
+                    self.queues[b as usize].push((OrderedFloat(gain), u));
+                }
+            }
+        }
+    }
+
+    /// Returns the best rated candidate for block `b` that still fits in it.
+    fn top(&mut self, b: bool, size_constraint: f32) -> Option<(f32, Index)> {
+        let queue = &mut self.queues[b as usize];
+        while let Some((OrderedFloat(g), v)) = queue.peek().copied() {
+            let stale =
+                self.labels[v as usize].is_some() || g != self.gains[b as usize][v as usize];
+            if stale || self.capacities[b as usize] + self.h.capacity(v) > size_constraint {
+                queue.pop();
+            } else {
+                return Some((g, v));
+            }
+        }
+        None
+    }
+
+    fn rate(&self, v: Index, b: bool) -> f32 {
+        self.h
+            .incident_nets(v)
+            .map(|e| {
+                let w = self.h.weight(e);
+                let others = self.h.pins_in_net(e).len() - 1;
+                let in_b = self
+                    .h
+                    .pins_in_net(e)
+                    .filter(|p| *p != v && self.labels[*p as usize] == Some(b))
+                    .count();
+                match self.gain {
+                    GhgGain::Fm if others == 0 => 0.0,
+                    GhgGain::Fm if in_b == others => w,
+                    GhgGain::Fm if in_b == 0 => -w,
+                    GhgGain::Fm => 0.0,
+                    GhgGain::MaxNet if in_b > 0 => w,
+                    GhgGain::MaxNet => 0.0,
+                    GhgGain::MaxPin => w * in_b as f32,
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghg_two_cliques() {
+        // Two cliques of five vertices, joined by a single net.
+        let mut nets = vec![[4, 5]];
+        for clique in [0..5, 5..10] {
+            for u in clique.clone() {
+                nets.extend((u + 1..clique.end).map(|v| [u, v]));
+            }
+        }
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let h = Bipartite::new(&[1.0; 10], &vec![1.0; nets.len()], &nets);
+
+        for gain in [GhgGain::Fm, GhgGain::MaxNet, GhgGain::MaxPin] {
+            for growth in [GhgGrowth::Global, GhgGrowth::RoundRobin] {
+                for seed in 0..10 {
+                    let rng = &mut StdRng::seed_from_u64(seed);
+                    let bipartition = greedy_hypergraph_growing(&h, 0.0, gain, growth, rng);
+                    assert_eq!(
+                        h.evaluate_bipartition(&bipartition),
+                        (5.0, 1.0),
+                        "{gain:?} {growth:?} {seed}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::iter::zip;
+
+use ordered_float::OrderedFloat;
+use rand::prelude::*;
+
+use crate::bipartite::*;
+
+const MAX_ITERATIONS: usize = 1000;
+const TOLERANCE: f32 = 1e-6;
+
+/// Spectral bipartitioning. Approximates the Fiedler vector of the weighted
+/// clique expansion's Laplacian `L` by power iteration on `sigma * I - L`,
+/// where `sigma` bounds the spectrum of `L`, keeping the iterate orthogonal to
+/// the constant vector. Vertices are then sorted by their Fiedler value and
+/// split at the position with the lowest cut among those where both blocks
+/// respect the size constraint, or at the capacity-weighted median if there
+/// is none. The random starting vector gives some diversity when the
+/// iteration hasn't converged.
+pub(crate) fn spectral(h: &Bipartite, epsilon: f32, rng: &mut impl Rng) -> Bipartition {
+    let pins: Vec<_> = h.pins().collect();
+    let mut compact = vec![0; h.pin_index_space_size()];
+    for (idx, v) in pins.iter().enumerate() {
+        compact[*v as usize] = idx;
+    }
+
+    // Every net of size n adds an n-clique with edge weight w / (n - 1). The
+    // adjacency lists may contain duplicate neighbors, which is harmless for
+    // matrix-vector products.
+    let mut adjacency = vec![vec![]; pins.len()];
+    let mut degrees = vec![0.0; pins.len()];
+    for e in h.nets() {
+        let len = h.pins_in_net(e).len();
+        if len < 2 {
+            continue;
+        }
+        let w = h.weight(e) / (len - 1) as f32;
+        for u in h.pins_in_net(e) {
+            let u = compact[u as usize];
+            for v in h.pins_in_net(e).map(|v| compact[v as usize]) {
+                if u != v {
+                    adjacency[u].push((v, w));
+                    degrees[u] += w;
+                }
+            }
+        }
+    }
+    let sigma = 2.0 * degrees.iter().copied().fold(0.0, f32::max);
+
+    let mut x: Vec<f32> = (0..pins.len()).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    normalize(&mut x);
+    for _ in 0..MAX_ITERATIONS {
+        let mut y: Vec<f32> = (0..pins.len())
+            .map(|u| {
+                let neighbors: f32 = adjacency[u].iter().map(|(v, w)| w * x[*v]).sum();
+                (sigma - degrees[u]) * x[u] + neighbors
+            })
+            .collect();
+        if !normalize(&mut y) {
+            break;
+        }
+        let change: f32 = zip(&x, &y).map(|(a, b)| (a - b) * (a - b)).sum();
+        x = y;
+        if change < TOLERANCE * TOLERANCE {
+            break;
+        }
+    }
+
+    let mut order: Vec<_> = (0..pins.len()).collect();
+    order.sort_by_key(|idx| OrderedFloat(x[*idx]));
+
+    // Sweep the split through the order, tracking the cut of every prefix. A
+    // net is cut once its first pin is in the prefix, until its last one is.
+    let target = h.total_capacity() / 2.0;
+    let size_constraint = h.size_constraint(epsilon);
+    let mut in_prefix = vec![0; h.net_index_space_size()];
+    let (mut capacity, mut cut) = (0.0, 0.0);
+    let mut best = None;
+    for (len, idx) in order.iter().enumerate().take(pins.len().saturating_sub(1)) {
+        let v = pins[*idx];
+        capacity += h.capacity(v);
+        for e in h.incident_nets(v) {
+            in_prefix[e as usize] += 1;
+            let net_len = h.pins_in_net(e).len();
+            if in_prefix[e as usize] == 1 && net_len > 1 {
+                cut += h.weight(e);
+            } else if in_prefix[e as usize] == net_len && net_len > 1 {
+                cut -= h.weight(e);
+            }
+        }
+        let feasible =
+            capacity <= size_constraint && h.total_capacity() - capacity <= size_constraint;
+        let key = (
+            !feasible,
+            OrderedFloat(if feasible { cut } else { 0.0 }),
+            OrderedFloat((capacity - target).abs()),
+        );
+        if best.as_ref().is_none_or(|(best_key, _)| key < *best_key) {
+            best = Some((key, len + 1));
+        }
+    }
+
+    let mut bipartition = vec![false; h.pin_index_space_size()];
+    let prefix = best.map_or(0, |(_, len)| len);
+    for idx in &order[..prefix] {
+        bipartition[pins[*idx] as usize] = true;
+    }
+    bipartition
+}
+
+/// Projects out the constant vector and scales to unit length. Returns false
+/// if nothing is left.
+fn normalize(x: &mut [f32]) -> bool {
+    let mean = x.iter().sum::<f32>() / x.len() as f32;
+    x.iter_mut().for_each(|a| *a -= mean);
+    let norm = x.iter().map(|a| a * a).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return false;
+    }
+    x.iter_mut().for_each(|a| *a /= norm);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spectral_path() {
+        let nets: Vec<_> = (0..19).map(|v| [v, v + 1]).collect();
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let h = Bipartite::new(&[1.0; 20], &[1.0; 19], &nets);
+        let bipartition = spectral(&h, 0.0, &mut StdRng::seed_from_u64(0));
+        assert_eq!(h.evaluate_bipartition(&bipartition), (10.0, 1.0));
+    }
+
+    #[test]
+    fn spectral_respects_size_constraint() {
+        // A path whose lightest edge is off center, but within epsilon.
+        let nets: Vec<_> = (0..19).map(|v| [v, v + 1]).collect();
+        let nets: Vec<&[_]> = nets.iter().map(|net| &net[..]).collect();
+        let mut weights = [1.0; 19];
+        weights[12] = 0.25;
+        weights[16] = 0.125;
+        let h = Bipartite::new(&[1.0; 20], &weights, &nets);
+        let rng = &mut StdRng::seed_from_u64(0);
+        let bipartition = spectral(&h, 0.3, rng);
+        assert_eq!(h.evaluate_bipartition(&bipartition), (13.0, 0.25));
+        let bipartition = spectral(&h, 0.0, rng);
+        assert_eq!(h.evaluate_bipartition(&bipartition), (10.0, 1.0));
+    }
+}
@@ -0,0 +1,610 @@
+//! Readers and writers for common hypergraph and partition file formats.
+
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::bipartite::Index;
+use crate::hypergraph::{Hypergraph, HypergraphBuilder};
+use crate::matrix::Matrix;
+
+mod bookshelf;
+mod snapshot;
+
+pub use bookshelf::{read_bookshelf, read_bookshelf_files, Bookshelf};
+pub use snapshot::{read_snapshot, write_snapshot};
+
+/// Reads a hypergraph in the hMETIS format: a header line with the number of
+/// nets, the number of vertices and an optional format code, one line of
+/// 1-based pins per net, then one capacity per vertex. Format code 1 means
+/// every net line starts with the net's weight, 10 means vertex capacities
+/// follow the nets, and 11 means both. Lines starting with `%` are comments.
+pub fn read_hmetis(reader: impl BufRead) -> io::Result<Hypergraph> {
+    read_hmetis_with_base(reader, 1)
+}
+
+/// Reads a hypergraph in the hMETIS format whose vertex IDs start at `base`,
+/// such as the 0-based files some Zoltan tools produce.
+pub fn read_hmetis_with_base(reader: impl BufRead, base: Index) -> io::Result<Hypergraph> {
+    let mut lines = Lines::new(reader);
+    let header = lines
+        .next_line()?
+        .ok_or_else(|| invalid("missing header"))?;
+    let mut header = header.split_whitespace();
+    let num_nets: usize = lines.parse(header.next())?;
+    let num_vertices: usize = lines.parse(header.next())?;
+    let format: u32 = header.next().map_or(Ok(0), |f| lines.parse(Some(f)))?;
+    let (net_weights, vertex_weights) = match format {
+        0 => (false, false),
+        1 => (true, false),
+        10 => (false, true),
+        11 => (true, true),
+        _ => return Err(lines.error(format!("unknown format code {format}"))),
+    };
+
+    let mut nets = Vec::with_capacity(num_nets);
+    for _ in 0..num_nets {
+        let line = lines
+            .next_line()?
+            .ok_or_else(|| invalid("fewer nets than announced"))?;
+        let mut fields = line.split_whitespace();
+        let weight: f32 = if net_weights {
+            lines.parse(fields.next())?
+        } else {
+            1.0
+        };
+        let pins = fields
+            .map(|pin| lines.parse_index(pin, base, num_vertices))
+            .collect::<io::Result<Vec<_>>>()?;
+        nets.push((weight, pins));
+    }
+
+    let mut capacities = vec![1.0; num_vertices];
+    if vertex_weights {
+        for capacity in capacities.iter_mut() {
+            let line = lines
+                .next_line()?
+                .ok_or_else(|| invalid("fewer vertex weights than vertices"))?;
+            *capacity = lines.parse(line.split_whitespace().next())?;
+        }
+    }
+
+    let mut h = Hypergraph::new(capacities);
+    for (weight, pins) in nets {
+        h.add_net(weight, &pins);
+    }
+    Ok(h)
+}
+
+/// Reads a graph in the METIS format as a hypergraph with a 2-pin net per
+/// edge. The header line holds the number of vertices and edges, and
+/// optionally a format code and the number of vertex weights. Then every
+/// vertex has a line with its 1-based neighbors, so an isolated vertex has an
+/// empty line. Format code `abc` means vertex lines start with the vertex
+/// size if `a` is 1, which is ignored, then with the vertex weights if `b` is
+/// 1, the first of which becomes the capacity, and that every neighbor is
+/// followed by the edge weight if `c` is 1. Self-loops are an error, as in
+/// METIS. Lines starting with `%` are comments.
+pub fn read_metis(reader: impl BufRead) -> io::Result<Hypergraph> {
+    let mut lines = Lines::new(reader);
+    let header = lines
+        .next_line()?
+        .ok_or_else(|| invalid("missing header"))?;
+    let mut header = header.split_whitespace();
+    let num_vertices: usize = lines.parse(header.next())?;
+    let num_edges: usize = lines.parse(header.next())?;
+    let format = header.next().unwrap_or("0");
+    if format.len() > 3 || format.chars().any(|c| c != '0' && c != '1') {
+        return Err(lines.error(format!("unknown format code {format}")));
+    }
+    let flag = |digit| format.bytes().rev().nth(digit) == Some(b'1');
+    let (edge_weights, vertex_weights, vertex_sizes) = (flag(0), flag(1), flag(2));
+    let num_constraints: usize = header.next().map_or(Ok(1), |f| lines.parse(Some(f)))?;
+
+    let mut capacities = vec![1.0; num_vertices];
+    let mut edges = vec![];
+    for (u, capacity) in capacities.iter_mut().enumerate() {
+        let line = lines
+            .next_line_or_empty()?
+            .ok_or_else(|| invalid("fewer vertex lines than vertices"))?;
+        let mut fields = line.split_whitespace();
+        if vertex_sizes {
+            lines.parse::<f32>(fields.next())?;
+        }
+        if vertex_weights {
+            *capacity = lines.parse(fields.next())?;
+            for _ in 1..num_constraints {
+                lines.parse::<f32>(fields.next())?;
+            }
+        }
+        while let Some(field) = fields.next() {
+            let v = lines.parse_index(field, 1, num_vertices)?;
+            let weight: f32 = if edge_weights {
+                lines.parse(fields.next())?
+            } else {
+                1.0
+            };
+            if u as Index == v {
+                return Err(lines.error(format!("self-loop on vertex {}", u + 1)));
+            }
+            // Every edge is listed by both endpoints, but becomes one net.
+            if (u as Index) < v {
+                edges.push((weight, [u as Index, v]));
+            }
+        }
+    }
+    if edges.len() != num_edges {
+        return Err(invalid(format!(
+            "expected {num_edges} edges, found {}",
+            edges.len()
+        )));
+    }
+
+    let mut h = Hypergraph::new(capacities);
+    for (weight, pins) in edges {
+        h.add_net(weight, &pins);
+    }
+    Ok(h)
+}
+
+/// Reads a graph from a CSV edge list, with a line `<source>,<target>` or
+/// `<source>,<target>,<weight>` per edge. Every edge becomes a 2-pin net with
+/// unit weight by default. Vertices are named after the names in the file,
+/// have unit capacity, and are numbered in order of first appearance.
+/// Self-loops are skipped, as they can't be cut. Lines starting with `#` are
+/// comments.
+pub fn read_edge_list(reader: impl BufRead) -> io::Result<Hypergraph> {
+    let mut lines = Lines::with_comment(reader, '#');
+    let mut builder = HypergraphBuilder::new();
+    while let Some(line) = lines.next_line()? {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (source, target, weight) = match fields[..] {
+            [source, target] => (source, target, 1.0),
+            [source, target, weight] => (source, target, lines.parse(Some(weight))?),
+            _ => return Err(lines.error("expected `<source>,<target>[,<weight>]`")),
+        };
+        if source == target {
+            builder.vertex(source, 1.0);
+        } else {
+            builder.net(weight, &[source, target]);
+        }
+    }
+    Ok(builder.build())
+}
+
+/// A hypergraph in PaToH's format, whose cells can have one weight per balance
+/// constraint. The partitioner only balances the first constraint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patoh {
+    /// The hypergraph, with the weights of the first constraint as capacities.
+    pub hypergraph: Hypergraph,
+    /// `constraints[c][v]` is the weight of cell `v` under constraint `c`.
+    /// Always has at least one constraint, the capacities.
+    pub constraints: Vec<Vec<f32>>,
+}
+
+impl From<Hypergraph> for Patoh {
+    fn from(hypergraph: Hypergraph) -> Self {
+        let constraints = vec![hypergraph.capacities().to_vec()];
+        Patoh {
+            hypergraph,
+            constraints,
+        }
+    }
+}
+
+/// Reads a hypergraph in PaToH's `.u` format: a header line with the index
+/// base (0 or 1), the number of cells, nets and pins, and optionally a weight
+/// scheme and the number of constraints, then one line of pins per net, then
+/// the cell weights. Scheme 1 means cells are weighted, 2 means every net line
+/// starts with the net's cost, and 3 means both. Cell weights list the weight
+/// of every constraint for every cell in turn, and may span lines freely.
+/// Lines starting with `%` are comments.
+pub fn read_patoh(reader: impl BufRead) -> io::Result<Patoh> {
+    let mut lines = Lines::new(reader);
+    let header = lines
+        .next_line()?
+        .ok_or_else(|| invalid("missing header"))?;
+    let mut header = header.split_whitespace();
+    let base: Index = lines.parse(header.next())?;
+    if base > 1 {
+        return Err(lines.error(format!("invalid index base {base}")));
+    }
+    let num_cells: usize = lines.parse(header.next())?;
+    let num_nets: usize = lines.parse(header.next())?;
+    let num_pins: usize = lines.parse(header.next())?;
+    let scheme: u32 = header.next().map_or(Ok(0), |f| lines.parse(Some(f)))?;
+    if scheme > 3 {
+        return Err(lines.error(format!("unknown weight scheme {scheme}")));
+    }
+    let num_constraints: usize = header.next().map_or(Ok(1), |f| lines.parse(Some(f)))?;
+    if num_constraints == 0 {
+        return Err(lines.error("no constraints"));
+    }
+
+    let mut nets = Vec::with_capacity(num_nets);
+    for _ in 0..num_nets {
+        let line = lines
+            .next_line()?
+            .ok_or_else(|| invalid("fewer nets than announced"))?;
+        let mut fields = line.split_whitespace();
+        let weight: f32 = if scheme >= 2 {
+            lines.parse(fields.next())?
+        } else {
+            1.0
+        };
+        let pins = fields
+            .map(|pin| lines.parse_index(pin, base, num_cells))
+            .collect::<io::Result<Vec<_>>>()?;
+        nets.push((weight, pins));
+    }
+    let found: usize = nets.iter().map(|(_, pins)| pins.len()).sum();
+    if found != num_pins {
+        return Err(invalid(format!(
+            "expected {num_pins} pins in total, found {found}"
+        )));
+    }
+
+    let mut constraints = vec![vec![1.0; num_cells]; num_constraints];
+    if scheme % 2 == 1 {
+        let mut weights = vec![];
+        while weights.len() < num_cells * num_constraints {
+            let line = lines
+                .next_line()?
+                .ok_or_else(|| invalid("fewer cell weights than cells"))?;
+            for field in line.split_whitespace() {
+                weights.push(lines.parse(Some(field))?);
+            }
+        }
+        for (idx, weight) in weights.into_iter().enumerate() {
+            constraints[idx % num_constraints][idx / num_constraints] = weight;
+        }
+    }
+
+    let mut hypergraph = Hypergraph::new(constraints[0].clone());
+    for (weight, pins) in nets {
+        hypergraph.add_net(weight, &pins);
+    }
+    Ok(Patoh {
+        hypergraph,
+        constraints,
+    })
+}
+
+/// Writes a hypergraph in PaToH's `.u` format with vertex IDs starting at
+/// `base`, with net costs and the weights of every constraint.
+pub fn write_patoh(mut writer: impl Write, patoh: &Patoh, base: Index) -> io::Result<()> {
+    let h = &patoh.hypergraph;
+    write!(
+        writer,
+        "{base} {} {} {} 3",
+        h.num_vertices(),
+        h.num_nets(),
+        h.num_pins()
+    )?;
+    if patoh.constraints.len() > 1 {
+        write!(writer, " {}", patoh.constraints.len())?;
+    }
+    writeln!(writer)?;
+    for (net, weight) in h.nets().zip(h.weights()) {
+        write!(writer, "{weight}")?;
+        for v in net {
+            write!(writer, " {}", v + base)?;
+        }
+        writeln!(writer)?;
+    }
+    for v in 0..h.num_vertices() {
+        let weights: Vec<_> = patoh
+            .constraints
+            .iter()
+            .map(|weights| weights[v].to_string())
+            .collect();
+        writeln!(writer, "{}", weights.join(" "))?;
+    }
+    writer.flush()
+}
+
+/// Reads the nonzero structure of a sparse matrix in the Matrix Market
+/// coordinate format. Values are ignored. Symmetric, skew-symmetric and
+/// Hermitian matrices only store one triangle, so their off-diagonal entries
+/// are mirrored.
+pub fn read_matrix_market(reader: impl BufRead) -> io::Result<Matrix> {
+    let mut reader = reader;
+    let mut banner = String::new();
+    reader.read_line(&mut banner)?;
+    let banner: Vec<_> = banner
+        .split_whitespace()
+        .map(|field| field.to_lowercase())
+        .collect();
+    let [magic, object, format, _field, symmetry] = &banner[..] else {
+        return Err(invalid("line 1: invalid Matrix Market banner"));
+    };
+    if magic != "%%matrixmarket" || object != "matrix" {
+        return Err(invalid("line 1: invalid Matrix Market banner"));
+    }
+    if format != "coordinate" {
+        return Err(invalid(format!("line 1: unsupported format `{format}`")));
+    }
+    let mirror = match symmetry.as_str() {
+        "general" => false,
+        "symmetric" | "skew-symmetric" | "hermitian" => true,
+        _ => return Err(invalid(format!("line 1: unknown symmetry `{symmetry}`"))),
+    };
+
+    let mut lines = Lines::new(reader);
+    lines.number = 1;
+    let size = lines
+        .next_line()?
+        .ok_or_else(|| invalid("missing size line"))?;
+    let mut size = size.split_whitespace();
+    let rows: usize = lines.parse(size.next())?;
+    let cols: usize = lines.parse(size.next())?;
+    let entries: usize = lines.parse(size.next())?;
+
+    let mut nonzeros = Vec::with_capacity(if mirror { 2 * entries } else { entries });
+    for _ in 0..entries {
+        let line = lines
+            .next_line()?
+            .ok_or_else(|| invalid("fewer entries than announced"))?;
+        let mut fields = line.split_whitespace();
+        let row = lines.parse_index(fields.next().unwrap_or(""), 1, rows)?;
+        let col = lines.parse_index(fields.next().unwrap_or(""), 1, cols)?;
+        nonzeros.push((row, col));
+        if mirror && row != col {
+            nonzeros.push((col, row));
+        }
+    }
+    Ok(Matrix {
+        rows,
+        cols,
+        nonzeros,
+    })
+}
+
+/// Writes a partition in terms of vertex names: every line holds the name of a
+/// vertex and its block, separated by a space.
+pub fn write_named_partition(
+    mut writer: impl Write,
+    names: &[String],
+    blocks: &[u32],
+) -> io::Result<()> {
+    for (name, block) in names.iter().zip(blocks) {
+        writeln!(writer, "{name} {block}")?;
+    }
+    writer.flush()
+}
+
+/// Reads a partition written by `write_named_partition`, mapping the names to
+/// the vertices of `h`. Every vertex must appear exactly once.
+pub fn read_named_partition(reader: impl BufRead, h: &Hypergraph) -> io::Result<Vec<u32>> {
+    let mut lines = Lines::new(reader);
+    let mut blocks = vec![None; h.num_vertices()];
+    while let Some(line) = lines.next_line()? {
+        let (name, block) = line
+            .rsplit_once(' ')
+            .ok_or_else(|| lines.error("missing block"))?;
+        let v = h
+            .vertex_id(name)
+            .ok_or_else(|| lines.error(format!("unknown vertex `{name}`")))?;
+        if blocks[v as usize]
+            .replace(lines.parse(Some(block))?)
+            .is_some()
+        {
+            return Err(lines.error(format!("vertex `{name}` appears twice")));
+        }
+    }
+    blocks
+        .into_iter()
+        .enumerate()
+        .map(|(v, block)| {
+            block.ok_or_else(|| {
+                let name = h.vertex_name(v as Index).unwrap_or_default();
+                invalid(format!("vertex `{name}` is missing"))
+            })
+        })
+        .collect()
+}
+
+/// Writes a partition in the hMETIS format: the block of every vertex on its
+/// own line.
+pub fn write_partition(mut writer: impl Write, blocks: &[u32]) -> io::Result<()> {
+    for block in blocks {
+        writeln!(writer, "{block}")?;
+    }
+    writer.flush()
+}
+
+/// Reads a partition written by `write_partition`.
+pub fn read_partition(reader: impl BufRead) -> io::Result<Vec<u32>> {
+    let mut lines = Lines::new(reader);
+    let mut blocks = vec![];
+    while let Some(line) = lines.next_line()? {
+        blocks.push(lines.parse(Some(line.trim()))?);
+    }
+    Ok(blocks)
+}
+
+/// Iterates over the non-empty, non-comment lines of a file, keeping track of
+/// the line number for error messages.
+struct Lines<R> {
+    reader: R,
+    line: String,
+    number: usize,
+    comment: char,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn new(reader: R) -> Self {
+        Self::with_comment(reader, '%')
+    }
+
+    fn with_comment(reader: R, comment: char) -> Self {
+        Lines {
+            reader,
+            line: String::new(),
+            number: 0,
+            comment,
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        self.read(true)
+    }
+
+    /// Like `next_line`, but returns empty lines too, for formats where they
+    /// carry meaning.
+    fn next_line_or_empty(&mut self) -> io::Result<Option<String>> {
+        self.read(false)
+    }
+
+    fn read(&mut self, skip_empty: bool) -> io::Result<Option<String>> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.number += 1;
+            let line = self.line.trim();
+            if !(line.is_empty() && skip_empty || line.starts_with(self.comment)) {
+                return Ok(Some(line.to_string()));
+            }
+        }
+    }
+
+    fn parse<T: FromStr>(&self, field: Option<&str>) -> io::Result<T> {
+        let field = field.ok_or_else(|| self.error("missing field"))?;
+        field
+            .parse()
+            .map_err(|_| self.error(format!("invalid number `{field}`")))
+    }
+
+    /// Parses a vertex ID starting at `base` into a 0-based one.
+    fn parse_index(&self, field: &str, base: Index, num_vertices: usize) -> io::Result<Index> {
+        let v: usize = self.parse(Some(field))?;
+        let base = base as usize;
+        if v < base || v - base >= num_vertices {
+            return Err(self.error(format!("vertex {v} out of range")));
+        }
+        Ok((v - base) as Index)
+    }
+
+    fn error(&self, msg: impl AsRef<str>) -> io::Error {
+        invalid(format!("line {}: {}", self.number, msg.as_ref()))
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmetis_weighted() {
+        let file = "% a comment\n4 7 11\n2 1 2\n3 1 7 5 6\n8 5 6 4\n7 2 3 4\n5\n1\n8\n7\n3\n9\n3\n";
+        let h = read_hmetis(file.as_bytes()).unwrap();
+        assert_eq!(h.num_vertices(), 7);
+        assert_eq!(h.weights(), [2.0, 3.0, 8.0, 7.0]);
+        assert_eq!(h.net(1), [0, 6, 4, 5]);
+        assert_eq!(h.capacities(), [5.0, 1.0, 8.0, 7.0, 3.0, 9.0, 3.0]);
+
+        assert!(read_hmetis("1 2\n1 3\n".as_bytes()).is_err());
+        assert!(read_hmetis("2 2\n1 2\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn metis_weighted() {
+        // A triangle with an isolated vertex, weighted vertices and edges.
+        let file = "% comment\n4 3 011\n2 2 1 3 4\n3 1 1 3 5\n1 1 4 2 5\n1\n";
+        let h = read_metis(file.as_bytes()).unwrap();
+        assert_eq!(h.capacities(), [2.0, 3.0, 1.0, 1.0]);
+        assert_eq!(h.weights(), [1.0, 4.0, 5.0]);
+        assert_eq!(h.nets().collect::<Vec<_>>(), [[0, 1], [0, 2], [1, 2]]);
+
+        // A path with an isolated vertex in the middle.
+        let h = read_metis("3 1\n3\n\n1\n".as_bytes()).unwrap();
+        assert_eq!(h.capacities(), [1.0; 3]);
+        assert_eq!(h.nets().collect::<Vec<_>>(), [[0, 2]]);
+
+        assert!(read_metis("2 2\n2\n1\n".as_bytes()).is_err());
+        let err = read_metis("2 2\n1 2\n1\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("self-loop on vertex 1"), "{err}");
+    }
+
+    #[test]
+    fn edge_list_names() {
+        let file = "# source,target,weight\nu1, u2\nu2,u3,2.5\nu3,u3\n";
+        let h = read_edge_list(file.as_bytes()).unwrap();
+        assert_eq!(h.vertex_names().unwrap(), ["u1", "u2", "u3"]);
+        assert_eq!(h.weights(), [1.0, 2.5]);
+        assert_eq!(h.nets().collect::<Vec<_>>(), [[0, 1], [1, 2]]);
+
+        assert!(read_edge_list("u1\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn hmetis_zero_based() {
+        let h = read_hmetis_with_base("2 3\n0 1\n1 2\n".as_bytes(), 0).unwrap();
+        assert_eq!(h.net(0), [0, 1]);
+        assert_eq!(h.net(1), [1, 2]);
+        assert!(read_hmetis_with_base("1 3\n1 3\n".as_bytes(), 0).is_err());
+    }
+
+    #[test]
+    fn patoh_round_trip() {
+        // Two constraints, with cell weights spread over lines.
+        let file = "% comment\n0 4 3 7 3 2\n2 0 1\n1 1 2 3\n5 0 3\n1 2 1 2\n3 1\n1 1\n";
+        let patoh = read_patoh(file.as_bytes()).unwrap();
+        let h = &patoh.hypergraph;
+        assert_eq!(h.weights(), [2.0, 1.0, 5.0]);
+        assert_eq!(h.net(1), [1, 2, 3]);
+        assert_eq!(h.capacities(), [1.0, 1.0, 3.0, 1.0]);
+        assert_eq!(patoh.constraints[1], [2.0, 2.0, 1.0, 1.0]);
+
+        for base in [0, 1] {
+            let mut file = vec![];
+            write_patoh(&mut file, &patoh, base).unwrap();
+            assert_eq!(read_patoh(&file[..]).unwrap(), patoh);
+        }
+
+        assert!(read_patoh("1 2 1 3\n1 2\n".as_bytes()).is_err());
+        assert!(read_patoh("1 2 1 2\n0 2\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn matrix_market_symmetric() {
+        let file = "%%MatrixMarket matrix coordinate real symmetric\n% comment\n3 3 3\n1 1 4.0\n2 1 -1.0\n3 2 2.5\n";
+        let matrix = read_matrix_market(file.as_bytes()).unwrap();
+        assert_eq!((matrix.rows, matrix.cols), (3, 3));
+        assert_eq!(matrix.nonzeros, [(0, 0), (1, 0), (0, 1), (2, 1), (1, 2)]);
+
+        let file = "%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n4\n";
+        assert!(read_matrix_market(file.as_bytes()).is_err());
+        let file = "%%MatrixMarket matrix coordinate pattern general\n2 2 1\n3 1\n";
+        assert!(read_matrix_market(file.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn partition_round_trip() {
+        let blocks = vec![0, 2, 1, 1, 0];
+        let mut file = vec![];
+        write_partition(&mut file, &blocks).unwrap();
+        assert_eq!(read_partition(&file[..]).unwrap(), blocks);
+    }
+
+    #[test]
+    fn named_partition_round_trip() {
+        let mut builder = HypergraphBuilder::new();
+        builder.net(1.0, &["a", "b c", "d"]);
+        let h = builder.build();
+        let blocks = vec![1, 0, 1];
+        let mut file = vec![];
+        write_named_partition(&mut file, h.vertex_names().unwrap(), &blocks).unwrap();
+        assert_eq!(read_named_partition(&file[..], &h).unwrap(), blocks);
+
+        assert!(read_named_partition(&b"a 1\nd 1\n"[..], &h).is_err());
+        assert!(read_named_partition(&b"a 1\nb c 0\nd 1\na 0\n"[..], &h).is_err());
+        assert!(read_named_partition(&b"a 1\nb c 0\ne 1\n"[..], &h).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use super::{invalid, Lines};
+use crate::bipartite::Index;
+use crate::hypergraph::Hypergraph;
+
+/// A netlist in the GSRC Bookshelf format used by the ISPD placement
+/// benchmarks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookshelf {
+    /// Cells are vertices with their area as capacity, named after the cells.
+    /// Nets are weighted by the `.wts` file, if any, and have unit weight
+    /// otherwise. Unnamed nets are named `net<ID>`.
+    pub hypergraph: Hypergraph,
+    /// The terminal cells, usually I/O pads, whose position is fixed.
+    pub terminals: Vec<Index>,
+}
+
+/// Reads the netlist listed by a Bookshelf `.aux` file, from the `.nodes`,
+/// `.nets` and optional `.wts` files next to it.
+pub fn read_bookshelf(aux: impl AsRef<Path>) -> io::Result<Bookshelf> {
+    let aux = aux.as_ref();
+    let dir = aux.parent().unwrap_or(Path::new(""));
+    let mut lines = Lines::with_comment(BufReader::new(File::open(aux)?), '#');
+    let line = lines
+        .next_line()?
+        .ok_or_else(|| invalid("empty .aux file"))?;
+    let files = line
+        .split_once(':')
+        .ok_or_else(|| lines.error("expected `<kind> : <files>`"))?
+        .1;
+    let find = |extension: &str| {
+        files
+            .split_whitespace()
+            .find(|file| file.ends_with(extension))
+            .map(|file| dir.join(file))
+    };
+    let open = |path| File::open(path).map(BufReader::new);
+
+    let nodes = find(".nodes").ok_or_else(|| lines.error("no .nodes file listed"))?;
+    let nets = find(".nets").ok_or_else(|| lines.error("no .nets file listed"))?;
+    let wts = find(".wts")
+        .filter(|path| path.exists())
+        .map(open)
+        .transpose()?;
+    read_bookshelf_files(open(nodes)?, open(nets)?, wts)
+}
+
+/// Reads a Bookshelf netlist from the contents of its `.nodes`, `.nets` and
+/// optional `.wts` files. Weights of cells in the `.wts` file are ignored, as
+/// cell areas are used instead.
+pub fn read_bookshelf_files(
+    nodes: impl BufRead,
+    nets: impl BufRead,
+    wts: Option<impl BufRead>,
+) -> io::Result<Bookshelf> {
+    let mut cell_names = vec![];
+    let mut capacities = vec![];
+    let mut terminals = vec![];
+    let mut lines = Lines::with_comment(nodes, '#');
+    let mut num_nodes = None;
+    while let Some(line) = lines.next_line()? {
+        let fields = fields(&line);
+        match fields[..] {
+            ["UCLA", ..] | ["NumTerminals", ..] => {}
+            ["NumNodes", count] => num_nodes = Some(lines.parse::<usize>(Some(count))?),
+            [name, width, height, ref rest @ ..] => {
+                let width: f32 = lines.parse(Some(width))?;
+                let height: f32 = lines.parse(Some(height))?;
+                if rest
+                    .first()
+                    .is_some_and(|kind| kind.starts_with("terminal"))
+                {
+                    terminals.push(cell_names.len() as Index);
+                }
+                cell_names.push(name.to_string());
+                capacities.push(width * height);
+            }
+            _ => return Err(lines.error("expected `<name> <width> <height>`")),
+        }
+    }
+    if num_nodes.is_some_and(|count| count != cell_names.len()) {
+        return Err(invalid("number of nodes doesn't match NumNodes"));
+    }
+    let mut hypergraph = Hypergraph::new(capacities);
+    hypergraph.set_vertex_names(cell_names);
+
+    let mut net_names = vec![];
+    let mut pins: Vec<Vec<Index>> = vec![];
+    let mut lines = Lines::with_comment(nets, '#');
+    let mut degree = 0;
+    while let Some(line) = lines.next_line()? {
+        let fields = fields(&line);
+        match fields[..] {
+            ["UCLA", ..] | ["NumNets", ..] | ["NumPins", ..] => {}
+            ["NetDegree", count, ref name @ ..] if degree == 0 => {
+                degree = lines.parse(Some(count))?;
+                let name = name.first().map_or_else(
+                    || format!("net{}", net_names.len()),
+                    |name| name.to_string(),
+                );
+                net_names.push(name);
+                pins.push(Vec::with_capacity(degree));
+            }
+            [cell, ..] if degree > 0 => {
+                let v = hypergraph
+                    .vertex_id(cell)
+                    .ok_or_else(|| lines.error(format!("unknown cell `{cell}`")))?;
+                pins.last_mut().unwrap().push(v);
+                degree -= 1;
+            }
+            _ => return Err(lines.error("expected `NetDegree : <degree> [<name>]`")),
+        }
+    }
+    if degree > 0 {
+        return Err(invalid("last net has fewer pins than its degree"));
+    }
+
+    let mut weights = vec![1.0; net_names.len()];
+    if let Some(wts) = wts {
+        let nets: HashMap<&str, usize> = net_names
+            .iter()
+            .enumerate()
+            .map(|(e, name)| (name.as_str(), e))
+            .collect();
+        let mut lines = Lines::with_comment(wts, '#');
+        while let Some(line) = lines.next_line()? {
+            match fields(&line)[..] {
+                ["UCLA", ..] => {}
+                [name, weight, ..] => {
+                    if let Some(e) = nets.get(name) {
+                        weights[*e] = lines.parse(Some(weight))?;
+                    }
+                }
+                _ => return Err(lines.error("expected `<name> <weight>`")),
+            }
+        }
+    }
+
+    for (pins, weight) in pins.iter().zip(weights) {
+        hypergraph.add_net(weight, pins);
+    }
+    hypergraph.set_net_names(net_names);
+    Ok(Bookshelf {
+        hypergraph,
+        terminals,
+    })
+}
+
+/// Splits a line into fields, treating the `:` separators as whitespace.
+fn fields(line: &str) -> Vec<&str> {
+    line.split(|c: char| c.is_whitespace() || c == ':')
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookshelf_netlist() {
+        let nodes = "UCLA nodes 1.0\n# comment\nNumNodes : 4\nNumTerminals : 1\n  a 2 3\n  b 1 1\n  c 4 1\n  p0 1 1 terminal\n";
+        let nets = "UCLA nets 1.0\nNumNets : 2\nNumPins : 5\nNetDegree : 3 n0\n  a I : 0.5 0.5\n  b O\n  p0 B\nNetDegree : 2\n  b I\n  c O\n";
+        let wts = "UCLA wts 1.0\n  n0 3\n  a 7\n";
+        let bookshelf =
+            read_bookshelf_files(nodes.as_bytes(), nets.as_bytes(), Some(wts.as_bytes())).unwrap();
+        let h = &bookshelf.hypergraph;
+        assert_eq!(h.capacities(), [6.0, 1.0, 4.0, 1.0]);
+        assert_eq!(h.weights(), [3.0, 1.0]);
+        assert_eq!(h.net(0), [0, 1, 3]);
+        assert_eq!(h.net(1), [1, 2]);
+        assert_eq!(h.vertex_names().unwrap(), ["a", "b", "c", "p0"]);
+        assert_eq!(h.net_names().unwrap(), ["n0", "net1"]);
+        assert_eq!(h.vertex_id("p0"), Some(3));
+        assert_eq!(bookshelf.terminals, [3]);
+
+        let nets = "NetDegree : 2\n  a I\n  d O\n";
+        assert!(read_bookshelf_files(nodes.as_bytes(), nets.as_bytes(), None::<&[u8]>).is_err());
+    }
+}
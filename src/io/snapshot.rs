@@ -0,0 +1,153 @@
+use std::io::{self, Read, Write};
+
+use super::invalid;
+use crate::bipartite::Index;
+use crate::hypergraph::Hypergraph;
+
+const MAGIC: &[u8; 4] = b"HPSN";
+const VERSION: u32 = 1;
+const VERTEX_NAMES: u8 = 1;
+const NET_NAMES: u8 = 2;
+
+/// Writes `h` in a compact binary format that is much faster to read than
+/// the text formats, e.g. to cache large inputs. All numbers are
+/// little-endian: the magic bytes `HPSN`, the version as a `u32`, the numbers
+/// of vertices, nets and pins as `u64`s, a flags byte (1 if the vertices are
+/// named, 2 if the nets are), the capacities and weights as `f32`s, `eptr` as
+/// `u64`s, `eind` as `u32`s, and finally the names, each as a `u32` length
+/// and UTF-8 bytes. Pass a buffered writer, as numbers are written one by
+/// one.
+pub fn write_snapshot(mut writer: impl Write, h: &Hypergraph) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    for count in [h.num_vertices(), h.num_nets(), h.num_pins()] {
+        writer.write_all(&(count as u64).to_le_bytes())?;
+    }
+    let vertex_names = h.vertex_names();
+    let net_names = h.net_names();
+    let mut flags = 0;
+    if vertex_names.is_some() {
+        flags |= VERTEX_NAMES;
+    }
+    if net_names.is_some() {
+        flags |= NET_NAMES;
+    }
+    writer.write_all(&[flags])?;
+    for x in h.capacities().iter().chain(h.weights()) {
+        writer.write_all(&x.to_le_bytes())?;
+    }
+    for x in h.eptr() {
+        writer.write_all(&(*x as u64).to_le_bytes())?;
+    }
+    for x in h.eind() {
+        writer.write_all(&x.to_le_bytes())?;
+    }
+    for name in vertex_names.into_iter().chain(net_names).flatten() {
+        let len = u32::try_from(name.len()).map_err(|_| invalid("name too long"))?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Reads a hypergraph written by `write_snapshot`.
+pub fn read_snapshot(mut reader: impl Read) -> io::Result<Hypergraph> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a hypergraph snapshot"));
+    }
+    let version = u32::from_le_bytes(read_array(&mut reader)?);
+    if version != VERSION {
+        return Err(invalid(format!("unsupported snapshot version {version}")));
+    }
+    let mut count = || {
+        let count = u64::from_le_bytes(read_array(&mut reader)?);
+        usize::try_from(count).map_err(|_| invalid("snapshot too large"))
+    };
+    let (num_vertices, num_nets, num_pins) = (count()?, count()?, count()?);
+    let [flags] = read_array(&mut reader)?;
+
+    let capacities = read_vec(&mut reader, num_vertices, f32::from_le_bytes)?;
+    let weights = read_vec(&mut reader, num_nets, f32::from_le_bytes)?;
+    let eptr_len = num_nets
+        .checked_add(1)
+        .ok_or_else(|| invalid("snapshot too large"))?;
+    let eptr = read_vec(&mut reader, eptr_len, u64::from_le_bytes)?;
+    let eptr = eptr.into_iter().map(|x| x as usize).collect();
+    let eind = read_vec(&mut reader, num_pins, Index::from_le_bytes)?;
+    let mut h = Hypergraph::from_parts(capacities, weights, eptr, eind).map_err(invalid)?;
+    if flags & VERTEX_NAMES != 0 {
+        h.set_vertex_names(read_names(&mut reader, num_vertices)?);
+    }
+    if flags & NET_NAMES != 0 {
+        h.set_net_names(read_names(&mut reader, num_nets)?);
+    }
+    Ok(h)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads `len` numbers of `N` bytes each. The buffer grows as data arrives,
+/// so a corrupt length can't make it allocate more than the input holds.
+fn read_vec<T, const N: usize>(
+    reader: &mut impl Read,
+    len: usize,
+    from_bytes: fn([u8; N]) -> T,
+) -> io::Result<Vec<T>> {
+    let size = len
+        .checked_mul(N)
+        .ok_or_else(|| invalid("snapshot too large"))?;
+    let mut bytes = vec![];
+    reader.take(size as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| from_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+fn read_names(reader: &mut impl Read, len: usize) -> io::Result<Vec<String>> {
+    (0..len)
+        .map(|_| {
+            let len = u32::from_le_bytes(read_array(reader)?) as usize;
+            let bytes = read_vec(reader, len, u8::from_le_bytes)?;
+            String::from_utf8(bytes).map_err(|_| invalid("name is not UTF-8"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hypergraph::HypergraphBuilder;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut h = Hypergraph::new(vec![1.0, 2.5, 3.0]);
+        h.add_net(2.0, &[0, 1, 2]);
+        h.add_net(1.0, &[]);
+        h.add_net(0.5, &[2, 0]);
+        let mut bytes = vec![];
+        write_snapshot(&mut bytes, &h).unwrap();
+        assert_eq!(read_snapshot(&bytes[..]).unwrap(), h);
+
+        let mut builder = HypergraphBuilder::new();
+        builder.named_net("n0", 1.0, &["a", "é"]);
+        let mut h = builder.build();
+        h.add_net(2.0, &[1]);
+        let mut bytes = vec![];
+        write_snapshot(&mut bytes, &h).unwrap();
+        assert_eq!(read_snapshot(&bytes[..]).unwrap(), h);
+
+        assert!(read_snapshot(&bytes[..bytes.len() - 1]).is_err());
+        bytes[0] = b'X';
+        assert!(read_snapshot(&bytes[..]).is_err());
+    }
+}
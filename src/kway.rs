@@ -0,0 +1,204 @@
+use std::time::Instant;
+
+use rand::prelude::*;
+
+use crate::bipartite::Index;
+use crate::bipartition_with_config;
+use crate::config::{Config, Objective};
+use crate::hypergraph::Hypergraph;
+use crate::observer::Timings;
+
+/// The outcome of a k-way partitioning run.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartitionResult {
+    /// The block of every vertex, in `0..k`.
+    pub blocks: Vec<u32>,
+    /// Capacity of the heaviest block.
+    pub imbalance: f32,
+    /// Weight of the nets spanning more than one block.
+    pub cut: f32,
+    /// Connectivity metric, see `Objective::Km1`.
+    pub km1: f32,
+    /// Whether any bisection was cut short by the time limit or cancellation.
+    pub truncated: bool,
+    /// Summed over all bisections.
+    pub timings: Timings,
+}
+
+impl PartitionResult {
+    /// The block of the vertex of `h` called `name`.
+    pub fn block_of(&self, h: &Hypergraph, name: &str) -> Option<u32> {
+        h.vertex_id(name).map(|v| self.blocks[v as usize])
+    }
+}
+
+/// Partitions `h` into `k` blocks by recursive bisection. Each block may hold
+/// at most `(1 + epsilon) / k` of the total capacity. The allowed imbalance of
+/// every bisection is chosen so that the imbalances of the levels of the
+/// recursion multiply up to `epsilon`. Cut nets are handled according to
+/// `config.objective`. Odd splits are balanced by an isolated dummy vertex
+/// that makes up the difference between the two halves.
+///
+/// # Panics
+///
+/// Panics if `k` is zero.
+pub fn partition(h: &Hypergraph, k: usize, config: &Config) -> PartitionResult {
+    assert!(k > 0, "cannot partition into zero blocks");
+    let depth = usize::BITS - (k - 1).leading_zeros();
+    let epsilon = (1.0 + config.epsilon).powf(1.0 / depth.max(1) as f32) - 1.0;
+    let rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut recursion = Recursion {
+        config,
+        epsilon,
+        start: Instant::now(),
+        rng,
+        blocks: vec![0; h.num_vertices()],
+        truncated: false,
+        timings: Timings::default(),
+    };
+    let vertices: Vec<_> = (0..h.num_vertices() as Index).collect();
+    recursion.bisect(h, &vertices, k, 0);
+
+    let blocks = recursion.blocks;
+    PartitionResult {
+        imbalance: h
+            .block_capacities(&blocks, k)
+            .into_iter()
+            .fold(0.0, f32::max),
+        cut: h.cut(&blocks),
+        km1: h.km1(&blocks),
+        blocks,
+        truncated: recursion.truncated,
+        timings: recursion.timings,
+    }
+}
+
+struct Recursion<'a> {
+    config: &'a Config,
+    epsilon: f32,
+    start: Instant,
+    rng: StdRng,
+    blocks: Vec<u32>,
+    truncated: bool,
+    timings: Timings,
+}
+
+impl Recursion<'_> {
+    /// Splits `h`, whose vertices are `vertices` in the input hypergraph, into
+    /// the `k` blocks starting at `first`.
+    fn bisect(&mut self, h: &Hypergraph, vertices: &[Index], k: usize, first: u32) {
+        if k == 1 || h.num_vertices() <= 1 {
+            for v in vertices {
+                self.blocks[*v as usize] = first;
+            }
+            return;
+        }
+
+        let (k1, k2) = (k.div_ceil(2), k / 2);
+        let mut capacities = h.capacities().to_vec();
+        if k1 != k2 {
+            let total: f32 = capacities.iter().sum();
+            capacities.push(total * (k1 - k2) as f32 / k as f32);
+        }
+        let nets: Vec<_> = h.nets().collect();
+        let config = Config {
+            epsilon: self.epsilon,
+            seed: Some(self.rng.gen()),
+            time_limit: self
+                .config
+                .time_limit
+                .map(|limit| limit.saturating_sub(self.start.elapsed())),
+            ..self.config.clone()
+        };
+        let result = bipartition_with_config(&capacities, h.weights(), &nets, &config);
+        self.truncated |= result.truncated;
+        self.timings += result.timings;
+
+        // The dummy vertex, if any, is in the smaller half.
+        let first_half = if k1 != k2 {
+            !result.partition[h.num_vertices()]
+        } else {
+            true
+        };
+        for (half, k, first) in [
+            (first_half, k1, first),
+            (!first_half, k2, first + k1 as u32),
+        ] {
+            let (sub, sub_vertices) = self.extract(h, vertices, |v| result.partition[v] == half);
+            self.bisect(&sub, &sub_vertices, k, first);
+        }
+    }
+
+    /// Builds the sub-hypergraph induced by the vertices of one half.
+    fn extract(
+        &self,
+        h: &Hypergraph,
+        vertices: &[Index],
+        in_half: impl Fn(usize) -> bool,
+    ) -> (Hypergraph, Vec<Index>) {
+        let mut local = vec![Index::MAX; h.num_vertices()];
+        let mut sub_vertices = vec![];
+        let mut capacities = vec![];
+        for v in (0..h.num_vertices()).filter(|v| in_half(*v)) {
+            local[v] = sub_vertices.len() as Index;
+            sub_vertices.push(vertices[v]);
+            capacities.push(h.capacities()[v]);
+        }
+
+        let mut sub = Hypergraph::new(capacities);
+        for (net, weight) in h.nets().zip(h.weights()) {
+            let pins: Vec<_> = net
+                .iter()
+                .map(|v| local[*v as usize])
+                .filter(|v| *v != Index::MAX)
+                .collect();
+            let cut = pins.len() < net.len();
+            if pins.len() >= 2 && !(cut && self.config.objective == Objective::Cut) {
+                sub.add_net(*weight, &pins);
+            }
+        }
+        (sub, sub_vertices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Clustering, Coarsening};
+
+    #[test]
+    fn partition_grid() {
+        // A 12x12 grid graph.
+        let mut h = Hypergraph::new(vec![1.0; 144]);
+        for row in 0..12 {
+            for col in 0..12 {
+                let v = row * 12 + col;
+                if col < 11 {
+                    h.add_net(1.0, &[v, v + 1]);
+                }
+                if row < 11 {
+                    h.add_net(1.0, &[v, v + 12]);
+                }
+            }
+        }
+
+        for (k, objective) in [(3, Objective::Cut), (4, Objective::Km1)] {
+            let config = Config {
+                epsilon: 0.1,
+                coarsening: Coarsening::Multilevel(Clustering::HeavyEdge),
+                objective,
+                seed: Some(3),
+                ..Config::default()
+            };
+            let result = partition(&h, k, &config);
+            assert!(result.blocks.iter().all(|block| (*block as usize) < k));
+            assert!(result.imbalance <= 1.1 * 144.0 / k as f32, "{result:?}");
+            assert!(result.cut <= result.km1);
+            assert_eq!(result.cut, h.cut(&result.blocks));
+        }
+    }
+}
@@ -1,15 +1,96 @@
-#![feature(let_chains)]
 mod bipartite;
+mod budget;
 mod coarsen;
+mod config;
+mod evolutionary;
+#[cfg(feature = "capi")]
+mod ffi;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod hypergraph;
 mod initial;
+pub mod io;
+mod kway;
+mod matrix;
+mod metrics;
+mod observer;
+mod preprocess;
 mod uncoarsen;
 
+use ordered_float::OrderedFloat;
 use rand::prelude::*;
+use rayon::prelude::*;
 
-use bipartite::{Bipartite, Bipartition, Index};
-use coarsen::coarsen;
+use bipartite::Bipartite;
+use budget::Budget;
+use coarsen::{coarsen, coarsen_multilevel};
+use evolutionary::evolve;
 use initial::initial_partitioning;
-use uncoarsen::uncoarsen;
+use observer::Progress;
+use preprocess::{remove_large_nets, remove_redundant_nets};
+use uncoarsen::{uncoarsen, uncoarsen_multilevel};
+
+pub use bipartite::{Bipartition, Index};
+pub use budget::CancellationToken;
+pub use config::*;
+#[cfg(feature = "petgraph")]
+pub use graph::partition_graph;
+pub use hypergraph::{Hypergraph, HypergraphBuilder};
+pub use initial::{
+    BfsPartitioner, CoarseHypergraph, GhgGain, GhgGrowth, GhgPartitioner, InitialPartitioner,
+    LabelPropagationPartitioner, RandomPartitioner, SpectralPartitioner,
+};
+pub use kway::{partition, PartitionResult};
+pub use matrix::{Matrix, MatrixModel, Ownership};
+pub use metrics::{validate, InvalidPartition, Metrics};
+pub use observer::{Event, Observer, Timings};
+
+/// The outcome of a partitioning run.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BipartitionResult {
+    pub partition: Bipartition,
+    /// Capacity of the heaviest block.
+    pub imbalance: f32,
+    /// Weight of the cut nets.
+    pub cut: f32,
+    /// Spread of the cut over all tries, feasible or not.
+    pub cuts: ObjectiveStats,
+    /// Whether the time limit ran out or the run was cancelled. The partition
+    /// is complete, but may not be refined.
+    pub truncated: bool,
+    pub timings: Timings,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectiveStats {
+    pub min: f32,
+    pub mean: f32,
+    pub max: f32,
+}
+
+impl ObjectiveStats {
+    /// Summarizes a nonempty sequence of objectives. The mean is accumulated in
+    /// `f64` and clamped, so rounding can't push it outside `[min, max]`.
+    fn new(values: impl Iterator<Item = f32>) -> Self {
+        let mut stats = ObjectiveStats {
+            min: f32::INFINITY,
+            mean: 0.0,
+            max: f32::NEG_INFINITY,
+        };
+        let mut sum = 0.0;
+        let mut count = 0;
+        for x in values {
+            stats.min = stats.min.min(x);
+            stats.max = stats.max.max(x);
+            sum += x as f64;
+            count += 1;
+        }
+        stats.mean = ((sum / count as f64) as f32).clamp(stats.min, stats.max);
+        stats
+    }
+}
 
 pub fn bipartition(
     capacities: &[f32],
@@ -17,26 +98,270 @@ pub fn bipartition(
     nets: &[&[Index]],
     epsilon: f32,
 ) -> (Bipartition, (f32, f32)) {
-    let mut h = Bipartite::new(capacities, weights, nets);
-    let size_constraint = h.size_constraint(epsilon);
-    let mementos = coarsen(&mut h);
-    let mut bipart = initial_partitioning(&h, epsilon);
-    uncoarsen(&mut h, size_constraint, mementos, &mut bipart);
-    let eval = h.evaluate_bipartition(&bipart);
-    (bipart, eval)
+    let config = Config {
+        epsilon,
+        ..Config::default()
+    };
+    let result = bipartition_with_config(capacities, weights, nets, &config);
+    (result.partition, (result.imbalance, result.cut))
+}
+
+pub fn bipartition_with_config(
+    capacities: &[f32],
+    weights: &[f32],
+    nets: &[&[Index]],
+    config: &Config,
+) -> BipartitionResult {
+    bipartition_nets(capacities, weights, nets, None, config)
+}
+
+/// A hypergraph borrowed from the caller in the compressed sparse row layout
+/// of hMETIS: the pins of net `e` are `eind[eptr[e]..eptr[e + 1]]`.
+#[derive(Clone, Copy, Debug)]
+pub struct Csr<'a> {
+    pub capacities: &'a [f32],
+    pub weights: &'a [f32],
+    pub eptr: &'a [usize],
+    pub eind: &'a [Index],
+    /// The nets of every vertex as `(vptr, vind)`, in the same layout and in
+    /// ascending order, if the caller has them. Saves transposing the nets,
+    /// but requires that no net lists a pin twice. They are checked against
+    /// the nets in linear time.
+    pub incidence: Option<(&'a [usize], &'a [Index])>,
+}
+
+/// Like `bipartition_with_config`, but takes the hypergraph as flat CSR
+/// arrays rather than a slice per net. Note that `remove_redundant_nets` and
+/// `max_net_size` build their reduced hypergraph from a separate `Vec` per
+/// net, so turn both off to avoid that copy on large inputs.
+///
+/// # Panics
+///
+/// Panics if `eptr` doesn't hold one more entry than there are nets, or
+/// doesn't ascend within `eind`, or if the incidence isn't the transpose of
+/// the nets.
+pub fn bipartition_csr(h: &Csr, config: &Config) -> BipartitionResult {
+    let nets: Vec<&[Index]> = h.eptr.windows(2).map(|w| &h.eind[w[0]..w[1]]).collect();
+    bipartition_nets(h.capacities, h.weights, &nets, h.incidence, config)
+}
+
+fn bipartition_nets(
+    capacities: &[f32],
+    weights: &[f32],
+    nets: &[&[Index]],
+    incidence: Option<(&[usize], &[Index])>,
+    config: &Config,
+) -> BipartitionResult {
+    let partition = || {
+        let budget = Budget::new(config);
+        let progress = Progress::new(config);
+        let (original, reduced) = progress.time(
+            |timings| &mut timings.preprocessing,
+            || preprocess(capacities, weights, nets, incidence, config),
+        );
+        best_of(
+            &original,
+            reduced.as_ref().unwrap_or(&original),
+            config,
+            &budget,
+            &progress,
+        )
+    };
+    match &config.thread_pool {
+        Some(pool) => pool.install(partition),
+        None => partition(),
+    }
+}
+
+/// Builds the hypergraph as given, and a reduced copy for partitioning if any
+/// preprocessing is enabled.
+fn preprocess(
+    capacities: &[f32],
+    weights: &[f32],
+    nets: &[&[Index]],
+    incidence: Option<(&[usize], &[Index])>,
+    config: &Config,
+) -> (Bipartite, Option<Bipartite>) {
+    let original = match incidence {
+        Some((vptr, vind)) => Bipartite::with_incidence(capacities, weights, nets, vptr, vind),
+        None => Bipartite::new(capacities, weights, nets),
+    };
+    let preprocess = config.remove_redundant_nets || config.max_net_size.is_some();
+    let reduced = preprocess.then(|| {
+        let mut weights = weights.to_vec();
+        let mut nets = nets
+            .par_iter()
+            .map(|net| {
+                let mut net = net.to_vec();
+                net.sort_unstable();
+                net.dedup();
+                net
+            })
+            .collect();
+        if let Some(max_size) = config.max_net_size {
+            (weights, nets) = remove_large_nets(weights, nets, max_size);
+        }
+        if config.remove_redundant_nets {
+            (weights, nets) = remove_redundant_nets(weights, nets);
+        }
+        let nets: Vec<&[_]> = nets.iter().map(|net| &**net).collect();
+        Bipartite::new(capacities, &weights, &nets)
+    });
+    (original, reduced)
+}
+
+/// Runs `config.repetitions` independently seeded tries on `h`, and keeps the
+/// best feasible one. Objectives are reported on `original`, which `h` may be
+/// a preprocessed version of. Once the budget is exhausted, tries that haven't
+/// started yet are skipped.
+fn best_of(
+    original: &Bipartite,
+    h: &Bipartite,
+    config: &Config,
+    budget: &Budget,
+    progress: &Progress,
+) -> BipartitionResult {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let seeds: Vec<u64> = (0..config.repetitions.max(1)).map(|_| rng.gen()).collect();
+    let try_once = |(idx, seed)| {
+        if idx > 0 && budget.exhausted() {
+            return None;
+        }
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let bipart = match &config.evolutionary {
+            Some(evolutionary) => evolve(original, h, config, evolutionary, budget, progress, rng),
+            None => run(h, config, budget, progress, rng),
+        };
+        let eval = original.evaluate_bipartition(&bipart);
+        Some((bipart, eval))
+    };
+    let tries: Vec<_> = if config.parallel_repetitions {
+        seeds
+            .into_par_iter()
+            .enumerate()
+            .filter_map(try_once)
+            .collect()
+    } else {
+        seeds.into_iter().enumerate().filter_map(try_once).collect()
+    };
+
+    let cuts = ObjectiveStats::new(tries.iter().map(|(_, (_, cut))| *cut));
+    let size_constraint = h.size_constraint(config.epsilon);
+    let (partition, (imbalance, cut)) = tries
+        .into_iter()
+        .min_by_key(|(_, (imbalance, cut))| {
+            (
+                OrderedFloat(imbalance.max(size_constraint)),
+                OrderedFloat(*cut),
+            )
+        })
+        .unwrap();
+    let truncated = budget.truncated();
+    progress.notify(|| Event::Finished {
+        imbalance,
+        cut,
+        truncated,
+    });
+    BipartitionResult {
+        partition,
+        imbalance,
+        cut,
+        cuts,
+        truncated,
+        timings: progress.timings(),
+    }
+}
+
+fn run(
+    h: &Bipartite,
+    config: &Config,
+    budget: &Budget,
+    progress: &Progress,
+    rng: &mut impl Rng,
+) -> Bipartition {
+    let size_constraint = h.size_constraint(config.epsilon);
+    let initial = |coarsest: &Bipartite, rng: &mut _| {
+        let bipart = progress.time(
+            |timings| &mut timings.initial,
+            || initial_partitioning(coarsest, config, budget, rng),
+        );
+        progress.notify(|| {
+            let (imbalance, cut) = coarsest.evaluate_bipartition(&bipart);
+            Event::InitialPartition {
+                num_vertices: coarsest.num_pins(),
+                imbalance,
+                cut,
+            }
+        });
+        bipart
+    };
+    match config.coarsening {
+        Coarsening::NLevel => {
+            let (mut h, mementos) = progress.time(
+                |timings| &mut timings.coarsening,
+                || {
+                    let mut h = h.clone();
+                    let mementos = coarsen(&mut h, budget, progress);
+                    (h, mementos)
+                },
+            );
+            let mut bipart = initial(&h, rng);
+            progress.time(
+                |timings| &mut timings.refinement,
+                || {
+                    uncoarsen(
+                        &mut h,
+                        size_constraint,
+                        mementos,
+                        budget,
+                        progress,
+                        &mut bipart,
+                    )
+                },
+            );
+            bipart
+        }
+        Coarsening::Multilevel(clustering) => {
+            let levels = progress.time(
+                |timings| &mut timings.coarsening,
+                || coarsen_multilevel(h, clustering, None, budget, progress, rng),
+            );
+            let coarsest = levels.last().map_or(h, |level| &level.h);
+            let bipart = initial(coarsest, rng);
+            progress.time(
+                |timings| &mut timings.refinement,
+                || {
+                    uncoarsen_multilevel(
+                        h,
+                        size_constraint,
+                        &levels,
+                        config.refinement,
+                        budget,
+                        progress,
+                        bipart,
+                    )
+                },
+            )
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
 
-    use ordered_float::OrderedFloat;
-    use rayon::prelude::*;
+    use rayon::ThreadPoolBuilder;
 
     use super::*;
 
-    #[test]
-    fn bipartition_random_hypergraph() {
+    fn random_hypergraph() -> (Vec<f32>, Vec<f32>, Vec<Vec<Index>>) {
         let num_v = 2000;
         let num_e = 500;
         let max_net_size = 4;
@@ -51,6 +376,28 @@ mod tests {
                 pins.into_iter().collect()
             })
             .collect();
+        (capacities, weights, nets)
+    }
+
+    /// Eight cliques of 20 unit vertices in a ring, each also covered by one
+    /// net, with consecutive cliques joined by a single net. The best
+    /// bisection cuts two of the joining nets.
+    fn ring_of_cliques() -> (Vec<f32>, Vec<f32>, Vec<Vec<Index>>) {
+        let mut nets = vec![];
+        for clique in 0..8 {
+            let vertices: Vec<Index> = (clique * 20..clique * 20 + 20).collect();
+            for (idx, u) in vertices.iter().enumerate() {
+                nets.extend(vertices[idx + 1..].iter().map(|v| vec![*u, *v]));
+            }
+            nets.push(vertices);
+            nets.push(vec![clique * 20, (clique + 1) % 8 * 20 + 1]);
+        }
+        (vec![1.0; 160], vec![1.0; nets.len()], nets)
+    }
+
+    #[test]
+    fn bipartition_random_hypergraph() {
+        let (capacities, weights, nets) = random_hypergraph();
         let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
         let best_eval = (0..16)
             .into_par_iter()
@@ -65,4 +412,379 @@ mod tests {
             best_eval.1 / weights.iter().sum::<f32>(),
         );
     }
+
+    #[test]
+    fn bipartition_multilevel() {
+        let (capacities, weights, nets) = random_hypergraph();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+        for (clustering, refinement) in [
+            (Clustering::Matching, Refinement::Fm),
+            (Clustering::HeavyEdge, Refinement::LabelPropagation),
+        ] {
+            let config = Config {
+                epsilon: 0.1,
+                coarsening: Coarsening::Multilevel(clustering),
+                refinement,
+                thread_pool: Some(pool.clone()),
+                ..Config::default()
+            };
+            let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+            assert_eq!(result.partition.len(), capacities.len());
+            assert!(result.imbalance <= 1.1 * capacities.iter().sum::<f32>() / 2.0);
+        }
+    }
+
+    #[test]
+    fn bipartition_repetitions() {
+        let (capacities, weights, nets) = random_hypergraph();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let config = Config {
+            epsilon: 0.1,
+            coarsening: Coarsening::Multilevel(Clustering::Matching),
+            repetitions: 4,
+            parallel_repetitions: true,
+            seed: Some(42),
+            ..Config::default()
+        };
+        // Timings are the only part of the result that may differ between runs.
+        let partition = || BipartitionResult {
+            timings: Timings::default(),
+            ..bipartition_with_config(&capacities, &weights, &nets_ref, &config)
+        };
+        let result = partition();
+        assert!(result.cuts.min <= result.cuts.mean && result.cuts.mean <= result.cuts.max);
+        assert!(result.cuts.min <= result.cut && result.cut <= result.cuts.max);
+        assert_eq!(result, partition());
+    }
+
+    #[test]
+    fn objective_stats_mean_within_bounds() {
+        // Summing these in f32 rounds the mean above the maximum.
+        let stats = ObjectiveStats::new([0.1; 7].into_iter());
+        assert_eq!((stats.min, stats.mean, stats.max), (0.1, 0.1, 0.1));
+        let stats = ObjectiveStats::new([1.0, 2.0, 6.0].into_iter());
+        assert_eq!((stats.min, stats.mean, stats.max), (1.0, 3.0, 6.0));
+    }
+
+    #[test]
+    fn bipartition_csr_input() {
+        let (capacities, weights, nets) = random_hypergraph();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let mut eptr = vec![0];
+        let mut eind = vec![];
+        let mut incidence = vec![vec![]; capacities.len()];
+        for (e, net) in nets.iter().enumerate() {
+            eind.extend_from_slice(net);
+            eptr.push(eind.len());
+            for v in net {
+                incidence[*v as usize].push(e as Index);
+            }
+        }
+        let vptr: Vec<usize> = std::iter::once(0)
+            .chain(incidence.iter().scan(0, |end, nets| {
+                *end += nets.len();
+                Some(*end)
+            }))
+            .collect();
+        let vind = incidence.concat();
+
+        let config = Config {
+            coarsening: Coarsening::Multilevel(Clustering::HeavyEdge),
+            seed: Some(3),
+            ..Config::default()
+        };
+        let partition = |result: BipartitionResult| (result.partition, result.cut);
+        let expected = partition(bipartition_with_config(
+            &capacities,
+            &weights,
+            &nets_ref,
+            &config,
+        ));
+        let mut csr = Csr {
+            capacities: &capacities,
+            weights: &weights,
+            eptr: &eptr,
+            eind: &eind,
+            incidence: None,
+        };
+        assert_eq!(partition(bipartition_csr(&csr, &config)), expected);
+        csr.incidence = Some((&vptr, &vind));
+        assert_eq!(partition(bipartition_csr(&csr, &config)), expected);
+    }
+
+    #[test]
+    fn bipartition_large_nets() {
+        let (capacities, weights, nets) = random_hypergraph();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let config = Config {
+            epsilon: 0.1,
+            max_net_size: Some(3),
+            ..Config::default()
+        };
+        let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+        let cut: f32 = std::iter::zip(&weights, &nets)
+            .filter(|(_, net)| net.iter().any(|v| result.partition[*v as usize]))
+            .filter(|(_, net)| net.iter().any(|v| !result.partition[*v as usize]))
+            .map(|(w, _)| w)
+            .sum();
+        assert!((cut - result.cut).abs() < 1e-3);
+    }
+
+    #[derive(Debug)]
+    struct FirstHalf(AtomicUsize);
+
+    impl InitialPartitioner for FirstHalf {
+        fn partition(&self, h: CoarseHypergraph, _: f32, _: &mut dyn RngCore) -> Bipartition {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let half = h.vertex_id_bound() / 2;
+            (0..h.vertex_id_bound()).map(|v| v < half).collect()
+        }
+    }
+
+    #[test]
+    fn custom_initial_partitioner() {
+        let (capacities, weights, nets) = random_hypergraph();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let partitioner = Arc::new(FirstHalf(AtomicUsize::new(0)));
+        let config = Config {
+            epsilon: 0.1,
+            portfolio: vec![(partitioner.clone() as Arc<dyn InitialPartitioner>, 3)],
+            ..Config::default()
+        };
+        bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+        assert_eq!(partitioner.0.load(Ordering::Relaxed), 3);
+    }
+
+    #[derive(Debug)]
+    struct UntilExhausted;
+
+    impl InitialPartitioner for UntilExhausted {
+        fn partition(&self, h: CoarseHypergraph, _: f32, _: &mut dyn RngCore) -> Bipartition {
+            while !h.exhausted() {
+                thread::yield_now();
+            }
+            vec![false; h.vertex_id_bound()]
+        }
+    }
+
+    #[test]
+    fn custom_initial_partitioner_budget() {
+        let config = Config {
+            portfolio: vec![(Arc::new(UntilExhausted) as Arc<dyn InitialPartitioner>, 1)],
+            time_limit: Some(Duration::from_millis(100)),
+            ..Config::default()
+        };
+        let result = bipartition_with_config(&[1.0; 4], &[1.0], &[&[0, 1, 2, 3]], &config);
+        assert!(result.truncated);
+    }
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl InitialPartitioner for Empty {
+        fn partition(&self, _: CoarseHypergraph, _: f32, _: &mut dyn RngCore) -> Bipartition {
+            vec![]
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty returned a bipartition of the wrong length")]
+    fn custom_initial_partitioner_wrong_length() {
+        let config = Config {
+            portfolio: vec![(Arc::new(Empty) as Arc<dyn InitialPartitioner>, 1)],
+            ..Config::default()
+        };
+        bipartition_with_config(&[1.0; 4], &[1.0], &[&[0, 1, 2, 3]], &config);
+    }
+
+    #[test]
+    fn bipartition_evolutionary() {
+        let (capacities, weights, nets) = ring_of_cliques();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let single = Config {
+            coarsening: Coarsening::Multilevel(Clustering::HeavyEdge),
+            seed: Some(5),
+            ..Config::default()
+        };
+        let config = Config {
+            evolutionary: Some(Evolutionary {
+                population_size: 4,
+                time_limit: Duration::from_millis(500),
+            }),
+            ..single.clone()
+        };
+        // The first individual is the single run with the same seed, and the
+        // best individual is never replaced.
+        let single = bipartition_with_config(&capacities, &weights, &nets_ref, &single);
+        let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+        assert!(result.imbalance <= 1.03 * capacities.iter().sum::<f32>() / 2.0);
+        assert!(result.cut <= single.cut);
+        assert_eq!(result.cut, 2.0);
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<Event>>);
+
+    impl Observer for Recorder {
+        fn notify(&self, event: &Event) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn bipartition_truncated() {
+        let (capacities, weights, nets) = ring_of_cliques();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        for config in [
+            Config {
+                time_limit: Some(Duration::ZERO),
+                repetitions: 3,
+                ..Config::default()
+            },
+            Config {
+                coarsening: Coarsening::Multilevel(Clustering::HeavyEdge),
+                cancellation: Some(cancellation.clone()),
+                ..Config::default()
+            },
+        ] {
+            let recorder = Arc::new(Recorder::default());
+            let config = Config {
+                observer: Some(recorder.clone()),
+                ..config
+            };
+            let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+            assert!(result.truncated);
+            assert_eq!(result.partition.len(), capacities.len());
+
+            // Coarsening stopped before the first contraction, and only the
+            // first repetition ran.
+            let events = recorder.0.lock().unwrap();
+            let initial: Vec<_> = events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::InitialPartition { num_vertices, .. } => Some(*num_vertices),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(initial, [capacities.len()]);
+        }
+
+        let config = Config {
+            coarsening: Coarsening::Multilevel(Clustering::Matching),
+            time_limit: Some(Duration::from_secs(600)),
+            ..Config::default()
+        };
+        let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+        assert!(!result.truncated);
+        assert_eq!(result.cut, 2.0);
+    }
+
+    #[test]
+    fn observer_events() {
+        let (capacities, weights, nets) = ring_of_cliques();
+        let nets_ref: Vec<&[_]> = nets.iter().map(|vec| &**vec).collect();
+        for coarsening in [
+            Coarsening::NLevel,
+            Coarsening::Multilevel(Clustering::Matching),
+        ] {
+            let recorder = Arc::new(Recorder::default());
+            let config = Config {
+                coarsening,
+                seed: Some(7),
+                observer: Some(recorder.clone()),
+                ..Config::default()
+            };
+            let result = bipartition_with_config(&capacities, &weights, &nets_ref, &config);
+            assert!(result.timings.coarsening > Duration::ZERO);
+            assert!(result.timings.initial > Duration::ZERO);
+            assert!(result.timings.refinement > Duration::ZERO);
+
+            // Coarsening shrinks the hypergraph down to the one that gets the
+            // initial partition, and uncoarsening grows it back to the input,
+            // whose final partition is the result.
+            let events = recorder.0.lock().unwrap();
+            let initial = events
+                .iter()
+                .position(|event| matches!(event, Event::InitialPartition { .. }))
+                .unwrap();
+            let coarsening: Vec<_> = events[..initial]
+                .iter()
+                .map(|event| match event {
+                    Event::Coarsening { num_vertices, .. } => *num_vertices,
+                    event => panic!("{event:?} during coarsening"),
+                })
+                .collect();
+            let Event::InitialPartition { num_vertices, .. } = events[initial] else {
+                unreachable!()
+            };
+            assert!(coarsening.is_sorted_by(|a, b| a >= b));
+            assert_eq!(coarsening.last(), Some(&num_vertices));
+            assert!(num_vertices < capacities.len());
+
+            let (last, uncoarsening) = events[initial + 1..].split_last().unwrap();
+            let uncoarsening: Vec<_> = uncoarsening
+                .iter()
+                .map(|event| match event {
+                    Event::Uncoarsening {
+                        num_vertices,
+                        imbalance,
+                        cut,
+                    } => (*num_vertices, *imbalance, *cut),
+                    event => panic!("{event:?} during uncoarsening"),
+                })
+                .collect();
+            assert!(uncoarsening.is_sorted_by(|a, b| a.0 <= b.0));
+            assert_eq!(
+                uncoarsening.last(),
+                Some(&(capacities.len(), result.imbalance, result.cut))
+            );
+            assert_eq!(
+                *last,
+                Event::Finished {
+                    imbalance: result.imbalance,
+                    cut: result.cut,
+                    truncated: false,
+                }
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_and_result_serde() {
+        let config: Config =
+            serde_json::from_str(r#"{"epsilon": 0.1, "objective": "Cut", "seed": 3}"#).unwrap();
+        assert_eq!(config.epsilon, 0.1);
+        assert_eq!(config.objective, Objective::Cut);
+        assert_eq!(config.seed, Some(3));
+        assert_eq!(config.coarsening, Coarsening::NLevel);
+        assert_eq!(config.portfolio.len(), initial::default_portfolio().len());
+        let json = serde_json::to_string(&config).unwrap();
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.objective, Objective::Cut);
+
+        let result = BipartitionResult {
+            partition: vec![true, false],
+            imbalance: 1.0,
+            cut: 2.0,
+            cuts: ObjectiveStats {
+                min: 2.0,
+                mean: 2.5,
+                max: 3.0,
+            },
+            truncated: false,
+            timings: Timings {
+                coarsening: Duration::from_millis(3),
+                ..Timings::default()
+            },
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BipartitionResult>(&json).unwrap(),
+            result
+        );
+    }
 }
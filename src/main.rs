@@ -0,0 +1,352 @@
+//! Command-line front end, in the spirit of `hmetis` and `KaHyPar`: reads an
+//! hMETIS, PaToH, Matrix Market or Bookshelf hypergraph, or a METIS or CSV
+//! edge list graph, partitions it, and writes `<hypergraph>.part.<k>`, with
+//! a vertex name before every block if the vertices are named. The `validate`
+//! subcommand checks and measures an existing partition instead.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use hpart::io::{
+    read_bookshelf, read_edge_list, read_hmetis_with_base, read_matrix_market, read_metis,
+    read_named_partition, read_partition, read_patoh, read_snapshot, write_named_partition,
+    write_partition,
+};
+use hpart::{partition, validate, Config, Hypergraph, Index, MatrixModel, Objective, Preset};
+
+const USAGE: &str = "\
+Usage: hpart <hypergraph> <k> [options]
+       hpart validate <hypergraph> <partition> [options]
+
+Input options:
+  -f, --format <format>    hmetis, patoh, mtx, bookshelf, metis, edges or
+                           snapshot [default: patoh for .u files, mtx for .mtx
+                           files, bookshelf for .aux files, metis for .graph
+                           files, edges for .csv files, hmetis otherwise]
+  -b, --base <base>        First vertex ID of hMETIS files, 0 or 1 [default: 1].
+                           PaToH files give their own base
+  -m, --model <model>      Hypergraph model of Matrix Market files: column, row
+                           or fine [default: column]. Row and column vertices
+                           are weighted by their number of nonzeros
+
+Partition options:
+  -e, --epsilon <eps>      Allowed imbalance [default: 0.03]
+  -o, --objective <obj>    cut or km1 [default: km1]
+  -s, --seed <seed>        Seed for all random choices
+  -p, --preset <preset>    fast, default or quality [default: default]
+  -t, --tries <n>          Independent tries per bisection [default: 1]
+  -h, --help               Print this message
+
+Validate options:
+  -k <k>                   Number of blocks [default: highest block ID + 1]
+  --no-empty               Reject partitions with empty blocks";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Hmetis,
+    Patoh,
+    MatrixMarket,
+    Bookshelf,
+    Metis,
+    EdgeList,
+    Snapshot,
+}
+
+/// Where to read the hypergraph from, and how.
+struct Input {
+    path: String,
+    format: Option<Format>,
+    base: Index,
+    model: MatrixModel,
+}
+
+impl Input {
+    /// Handles the input options shared by all commands. Returns false if
+    /// `arg` isn't one.
+    fn option(&mut self, arg: &str, value: &str) -> Result<bool, String> {
+        let invalid = || format!("invalid value `{value}` for `{arg}`");
+        match arg {
+            "-f" | "--format" => {
+                self.format = Some(match value {
+                    "hmetis" => Format::Hmetis,
+                    "patoh" => Format::Patoh,
+                    "mtx" => Format::MatrixMarket,
+                    "bookshelf" => Format::Bookshelf,
+                    "metis" => Format::Metis,
+                    "edges" => Format::EdgeList,
+                    "snapshot" => Format::Snapshot,
+                    _ => return Err(invalid()),
+                })
+            }
+            "-b" | "--base" => {
+                self.base = match value {
+                    "0" => 0,
+                    "1" => 1,
+                    _ => return Err(invalid()),
+                }
+            }
+            "-m" | "--model" => {
+                self.model = match value {
+                    "column" => MatrixModel::ColumnNet,
+                    "row" => MatrixModel::RowNet,
+                    "fine" => MatrixModel::FineGrain,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn read(&self) -> Result<Hypergraph, String> {
+        let path = &self.path;
+        let format = self.format.unwrap_or(if path.ends_with(".u") {
+            Format::Patoh
+        } else if path.ends_with(".mtx") {
+            Format::MatrixMarket
+        } else if path.ends_with(".aux") {
+            Format::Bookshelf
+        } else if path.ends_with(".graph") {
+            Format::Metis
+        } else if path.ends_with(".csv") {
+            Format::EdgeList
+        } else {
+            Format::Hmetis
+        });
+        let open = || File::open(path).map(BufReader::new);
+        match format {
+            Format::Hmetis => open().and_then(|reader| read_hmetis_with_base(reader, self.base)),
+            Format::Patoh => open().and_then(read_patoh).map(|patoh| patoh.hypergraph),
+            Format::MatrixMarket => open()
+                .and_then(read_matrix_market)
+                .map(|matrix| matrix.hypergraph(self.model, true)),
+            Format::Bookshelf => read_bookshelf(path).map(|bookshelf| bookshelf.hypergraph),
+            Format::Metis => open().and_then(read_metis),
+            Format::EdgeList => open().and_then(read_edge_list),
+            Format::Snapshot => open().and_then(read_snapshot),
+        }
+        .map_err(|err| format!("cannot read {path}: {err}"))
+    }
+}
+
+struct Args {
+    input: Input,
+    k: usize,
+    epsilon: f32,
+    objective: Objective,
+    seed: Option<u64>,
+    preset: Preset,
+    tries: usize,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let result = match args.split_first() {
+        Some((command, args)) if command == "validate" => run_validate(args),
+        _ => parse_args(&args).and_then(|args| run(&args)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("hpart: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut positional = vec![];
+    let mut input = Input {
+        path: String::new(),
+        format: None,
+        base: 1,
+        model: MatrixModel::ColumnNet,
+    };
+    let mut epsilon = 0.03;
+    let mut objective = Objective::Km1;
+    let mut seed = None;
+    let mut preset = Preset::Default;
+    let mut tries = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            positional.push(arg.clone());
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for `{arg}`\n\n{USAGE}"))?;
+        if input.option(arg, value)? {
+            continue;
+        }
+        let invalid = || format!("invalid value `{value}` for `{arg}`");
+        match arg.as_str() {
+            "-e" | "--epsilon" => epsilon = value.parse().map_err(|_| invalid())?,
+            "-o" | "--objective" => {
+                objective = match value.as_str() {
+                    "cut" => Objective::Cut,
+                    "km1" => Objective::Km1,
+                    _ => return Err(invalid()),
+                }
+            }
+            "-s" | "--seed" => seed = Some(value.parse().map_err(|_| invalid())?),
+            "-p" | "--preset" => {
+                preset = match value.as_str() {
+                    "fast" => Preset::Fast,
+                    "default" => Preset::Default,
+                    "quality" => Preset::Quality,
+                    _ => return Err(invalid()),
+                }
+            }
+            "-t" | "--tries" => tries = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown option `{arg}`\n\n{USAGE}")),
+        }
+    }
+
+    let [hypergraph, k] = &positional[..] else {
+        return Err(format!("expected a hypergraph and k\n\n{USAGE}"));
+    };
+    let k = match k.parse() {
+        Ok(k) if k > 0 => k,
+        _ => return Err(format!("invalid number of blocks `{k}`")),
+    };
+    input.path = hypergraph.clone();
+    Ok(Args {
+        input,
+        k,
+        epsilon,
+        objective,
+        seed,
+        preset,
+        tries,
+    })
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let start = Instant::now();
+    let h = args.input.read()?;
+    let read = start.elapsed();
+
+    let config = Config {
+        epsilon: args.epsilon,
+        objective: args.objective,
+        seed: args.seed,
+        repetitions: args.tries,
+        parallel_repetitions: true,
+        ..Config::from(args.preset)
+    };
+    let result = partition(&h, args.k, &config);
+
+    let output = format!("{}.part.{}", args.input.path, args.k);
+    let file = File::create(&output).map_err(|err| format!("cannot create {output}: {err}"))?;
+    let writer = BufWriter::new(file);
+    match h.vertex_names() {
+        Some(names) => write_named_partition(writer, names, &result.blocks),
+        None => write_partition(writer, &result.blocks),
+    }
+    .map_err(|err| format!("cannot write {output}: {err}"))?;
+
+    let total: f32 = h.capacities().iter().sum();
+    let imbalance = result.imbalance / (total / args.k as f32) - 1.0;
+    let timings = result.timings;
+    println!("hypergraph    {}", args.input.path);
+    println!("vertices      {}", h.num_vertices());
+    println!("nets          {}", h.num_nets());
+    println!("k             {}", args.k);
+    println!("cut           {}", result.cut);
+    println!("km1           {}", result.km1);
+    println!("imbalance     {imbalance:.4}");
+    println!("time          {}", seconds(start.elapsed()));
+    println!("  io          {}", seconds(read));
+    println!("  preprocess  {}", seconds(timings.preprocessing));
+    println!("  coarsening  {}", seconds(timings.coarsening));
+    println!("  initial     {}", seconds(timings.initial));
+    println!("  refinement  {}", seconds(timings.refinement));
+    println!("partition     {output}");
+    Ok(())
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+fn run_validate(args: &[String]) -> Result<(), String> {
+    let mut positional = vec![];
+    let mut input = Input {
+        path: String::new(),
+        format: None,
+        base: 1,
+        model: MatrixModel::ColumnNet,
+    };
+    let mut k = None;
+    let mut allow_empty = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--no-empty" {
+            allow_empty = false;
+            continue;
+        } else if !arg.starts_with('-') {
+            positional.push(arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for `{arg}`\n\n{USAGE}"))?;
+        if input.option(arg, value)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-k" => {
+                k = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid value `{value}` for `{arg}`"))?,
+                )
+            }
+            _ => return Err(format!("unknown option `{arg}`\n\n{USAGE}")),
+        }
+    }
+    let [hypergraph, partition] = &positional[..] else {
+        return Err(format!("expected a hypergraph and a partition\n\n{USAGE}"));
+    };
+
+    input.path = hypergraph.to_string();
+    let h = input.read()?;
+    let file = File::open(partition).map_err(|err| format!("cannot open {partition}: {err}"))?;
+    let reader = BufReader::new(file);
+    let blocks = match h.vertex_names() {
+        Some(_) => read_named_partition(reader, &h),
+        None => read_partition(reader),
+    }
+    .map_err(|err| format!("cannot read {partition}: {err}"))?;
+    let k = k.unwrap_or_else(|| blocks.iter().max().map_or(1, |max| *max as usize + 1));
+    let metrics = validate(&h, &blocks, k, allow_empty)
+        .map_err(|err| format!("invalid partition {partition}: {err}"))?;
+
+    println!("hypergraph    {hypergraph}");
+    println!("partition     {partition}");
+    println!("k             {k}");
+    println!("cut           {}", metrics.cut);
+    println!("km1           {}", metrics.km1);
+    println!("soed          {}", metrics.soed);
+    println!("imbalance     {:.4}", metrics.imbalance);
+    println!("boundary      {}", metrics.boundary_vertices);
+    println!("blocks");
+    for (block, capacity) in metrics.block_capacities.iter().enumerate() {
+        println!("  {block:<10}  {capacity}");
+    }
+    println!("connectivity");
+    for (connectivity, count) in metrics.connectivity.iter().enumerate() {
+        if *count > 0 {
+            println!("  {connectivity:<10}  {count}");
+        }
+    }
+    Ok(())
+}
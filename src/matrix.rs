@@ -0,0 +1,221 @@
+use crate::bipartite::Index;
+use crate::hypergraph::Hypergraph;
+
+/// The nonzero structure of a sparse matrix, with 0-based coordinates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    /// The `(row, column)` of every nonzero.
+    pub nonzeros: Vec<(Index, Index)>,
+}
+
+/// How a sparse matrix is turned into a hypergraph, for partitioning the work
+/// of a sparse matrix-vector product `y = Ax`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixModel {
+    /// Rows are vertices and columns are nets. Partitions the rows, and the
+    /// connectivity metric is the volume of communicating `x`.
+    ColumnNet,
+    /// Columns are vertices and rows are nets. Partitions the columns, and the
+    /// connectivity metric is the volume of communicating `y`.
+    RowNet,
+    /// Nonzeros are vertices, and every row and every column is a net, rows
+    /// first. Partitions the nonzeros, and the connectivity metric is the
+    /// total communication volume.
+    FineGrain,
+}
+
+/// Which block owns each part of a sparse matrix-vector product `y = Ax`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ownership {
+    /// The block computing each nonzero's product, in the order of
+    /// `Matrix::nonzeros`.
+    pub nonzeros: Vec<u32>,
+    /// The block owning each entry of `y`.
+    pub rows: Vec<u32>,
+    /// The block owning each entry of `x`.
+    pub cols: Vec<u32>,
+}
+
+impl Matrix {
+    /// Builds the hypergraph of the matrix under `model`. If `nnz_weights` is
+    /// set, row and column vertices are weighted by their number of nonzeros,
+    /// which is the work of their part of the product, but at least 1, as
+    /// coarsening divides by capacities. Otherwise, and for fine-grain
+    /// vertices, every vertex has unit capacity.
+    pub fn hypergraph(&self, model: MatrixModel, nnz_weights: bool) -> Hypergraph {
+        let (row_nnz, col_nnz) = self.nnz_counts();
+        let weights = |counts: Vec<usize>| {
+            counts
+                .into_iter()
+                .map(|count| {
+                    if nnz_weights {
+                        count.max(1) as f32
+                    } else {
+                        1.0
+                    }
+                })
+                .collect()
+        };
+        match model {
+            MatrixModel::ColumnNet => {
+                let mut h = Hypergraph::new(weights(row_nnz));
+                for pins in self.lines(|(row, col)| (col, row), self.cols) {
+                    h.add_net(1.0, &pins);
+                }
+                h
+            }
+            MatrixModel::RowNet => {
+                let mut h = Hypergraph::new(weights(col_nnz));
+                for pins in self.lines(|(row, col)| (row, col), self.rows) {
+                    h.add_net(1.0, &pins);
+                }
+                h
+            }
+            MatrixModel::FineGrain => {
+                let mut h = Hypergraph::new(vec![1.0; self.nonzeros.len()]);
+                let mut rows = vec![vec![]; self.rows];
+                let mut cols = vec![vec![]; self.cols];
+                for (idx, (row, col)) in self.nonzeros.iter().enumerate() {
+                    rows[*row as usize].push(idx as Index);
+                    cols[*col as usize].push(idx as Index);
+                }
+                for pins in rows.into_iter().chain(cols) {
+                    h.add_net(1.0, &pins);
+                }
+                h
+            }
+        }
+    }
+
+    /// Maps a partition of `self.hypergraph(model, _)` back to the matrix.
+    /// Nonzeros go with the vertex they belong to. Vector entries that no
+    /// vertex stands for go to the block holding most nonzeros of their row
+    /// or column, which is one of the blocks that needs them anyway, so the
+    /// communication volume stays at the connectivity metric. Entries of
+    /// empty rows or columns go to block 0.
+    pub fn ownership(&self, model: MatrixModel, blocks: &[u32]) -> Ownership {
+        let nonzeros: Vec<u32> = match model {
+            MatrixModel::ColumnNet => self
+                .nonzeros
+                .iter()
+                .map(|(row, _)| blocks[*row as usize])
+                .collect(),
+            MatrixModel::RowNet => self
+                .nonzeros
+                .iter()
+                .map(|(_, col)| blocks[*col as usize])
+                .collect(),
+            MatrixModel::FineGrain => blocks.to_vec(),
+        };
+        let rows = match model {
+            MatrixModel::ColumnNet => blocks.to_vec(),
+            _ => self.majority(&nonzeros, self.rows, |(row, _)| row),
+        };
+        let cols = match model {
+            MatrixModel::RowNet => blocks.to_vec(),
+            _ => self.majority(&nonzeros, self.cols, |(_, col)| col),
+        };
+        Ownership {
+            nonzeros,
+            rows,
+            cols,
+        }
+    }
+
+    fn nnz_counts(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut rows = vec![0; self.rows];
+        let mut cols = vec![0; self.cols];
+        for (row, col) in self.nonzeros.iter() {
+            rows[*row as usize] += 1;
+            cols[*col as usize] += 1;
+        }
+        (rows, cols)
+    }
+
+    /// Groups the nonzeros into `len` lines. `key` maps a nonzero to its line
+    /// and its vertex.
+    fn lines(&self, key: impl Fn((Index, Index)) -> (Index, Index), len: usize) -> Vec<Vec<Index>> {
+        let mut lines = vec![vec![]; len];
+        for nonzero in self.nonzeros.iter() {
+            let (line, v) = key(*nonzero);
+            lines[line as usize].push(v);
+        }
+        lines
+    }
+
+    /// The block holding most nonzeros of each of the `len` lines, with ties
+    /// going to the lower block.
+    fn majority(
+        &self,
+        owners: &[u32],
+        len: usize,
+        line: impl Fn((Index, Index)) -> Index,
+    ) -> Vec<u32> {
+        let mut counts: Vec<Vec<(u32, usize)>> = vec![vec![]; len];
+        for (nonzero, owner) in self.nonzeros.iter().zip(owners) {
+            let counts = &mut counts[line(*nonzero) as usize];
+            match counts.iter_mut().find(|(block, _)| block == owner) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*owner, 1)),
+            }
+        }
+        counts
+            .into_iter()
+            .map(|counts| {
+                counts
+                    .into_iter()
+                    .max_by_key(|(block, count)| (*count, std::cmp::Reverse(*block)))
+                    .map_or(0, |(block, _)| block)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_models() {
+        // [x x . .]
+        // [. x . x]
+        // [. . x .]
+        let matrix = Matrix {
+            rows: 3,
+            cols: 4,
+            nonzeros: vec![(0, 0), (0, 1), (1, 1), (1, 3), (2, 2)],
+        };
+
+        let h = matrix.hypergraph(MatrixModel::ColumnNet, true);
+        assert_eq!(h.capacities(), [2.0, 2.0, 1.0]);
+        assert_eq!(
+            h.nets().collect::<Vec<_>>(),
+            [&[0][..], &[0, 1], &[2], &[1]]
+        );
+        let ownership = matrix.ownership(MatrixModel::ColumnNet, &[0, 1, 1]);
+        assert_eq!(ownership.nonzeros, [0, 0, 1, 1, 1]);
+        assert_eq!(ownership.cols, [0, 0, 1, 1]);
+
+        let h = matrix.hypergraph(MatrixModel::RowNet, false);
+        assert_eq!(h.capacities(), [1.0; 4]);
+        assert_eq!(h.nets().collect::<Vec<_>>(), [&[0, 1][..], &[1, 3], &[2]]);
+
+        // An empty row still weighs 1.
+        let padded = Matrix {
+            rows: 4,
+            ..matrix.clone()
+        };
+        let h = padded.hypergraph(MatrixModel::ColumnNet, true);
+        assert_eq!(h.capacities(), [2.0, 2.0, 1.0, 1.0]);
+
+        let h = matrix.hypergraph(MatrixModel::FineGrain, true);
+        assert_eq!(h.num_vertices(), 5);
+        assert_eq!(h.num_nets(), 7);
+        assert_eq!(h.net(4), [1, 2]);
+        let ownership = matrix.ownership(MatrixModel::FineGrain, &[0, 1, 1, 1, 0]);
+        assert_eq!(ownership.rows, [0, 1, 0]);
+        assert_eq!(ownership.cols, [0, 1, 0, 1]);
+    }
+}
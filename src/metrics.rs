@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::bipartite::Index;
+use crate::hypergraph::Hypergraph;
+
+/// Why a partition is not a valid partition of a hypergraph into `k` blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidPartition {
+    /// The partition doesn't assign exactly one block to every vertex.
+    Length { expected: usize, found: usize },
+    /// A vertex is assigned to a block outside `0..k`.
+    BlockOutOfRange { vertex: Index, block: u32, k: usize },
+    /// No vertex is assigned to this block.
+    EmptyBlock(u32),
+}
+
+impl fmt::Display for InvalidPartition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidPartition::Length { expected, found } => {
+                write!(f, "expected {expected} block IDs, found {found}")
+            }
+            InvalidPartition::BlockOutOfRange { vertex, block, k } => {
+                write!(f, "vertex {vertex} is in block {block}, but k is {k}")
+            }
+            InvalidPartition::EmptyBlock(block) => write!(f, "block {block} is empty"),
+        }
+    }
+}
+
+impl Error for InvalidPartition {}
+
+/// Quality metrics of a k-way partition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// Total weight of the nets spanning more than one block.
+    pub cut: f32,
+    /// Sum over the nets of their weight times their connectivity minus one.
+    pub km1: f32,
+    /// Sum of external degrees: the sum over the cut nets of their weight
+    /// times their connectivity.
+    pub soed: f32,
+    /// Total capacity of each block.
+    pub block_capacities: Vec<f32>,
+    /// Capacity of the heaviest block relative to the average, minus one.
+    pub imbalance: f32,
+    /// Number of vertices with at least one incident cut net.
+    pub boundary_vertices: usize,
+    /// Number of nets spanning each number of blocks, indexed by
+    /// connectivity. Nets without pins have connectivity zero.
+    pub connectivity: Vec<usize>,
+}
+
+/// Checks that `blocks` assigns every vertex of `h` to one of `k` blocks, and
+/// if `allow_empty` is false, that every block has a vertex. Computes the
+/// metrics of valid partitions. Works on partitions from any tool.
+pub fn validate(
+    h: &Hypergraph,
+    blocks: &[u32],
+    k: usize,
+    allow_empty: bool,
+) -> Result<Metrics, InvalidPartition> {
+    if blocks.len() != h.num_vertices() {
+        return Err(InvalidPartition::Length {
+            expected: h.num_vertices(),
+            found: blocks.len(),
+        });
+    }
+    if let Some((v, block)) = blocks
+        .iter()
+        .enumerate()
+        .find(|(_, block)| **block as usize >= k)
+    {
+        return Err(InvalidPartition::BlockOutOfRange {
+            vertex: v as Index,
+            block: *block,
+            k,
+        });
+    }
+    let mut sizes = vec![0; k];
+    for block in blocks {
+        sizes[*block as usize] += 1;
+    }
+    if !allow_empty {
+        if let Some(block) = sizes.iter().position(|size| *size == 0) {
+            return Err(InvalidPartition::EmptyBlock(block as u32));
+        }
+    }
+
+    let mut metrics = Metrics {
+        cut: 0.0,
+        km1: 0.0,
+        soed: 0.0,
+        block_capacities: h.block_capacities(blocks, k),
+        imbalance: 0.0,
+        boundary_vertices: 0,
+        connectivity: vec![0; k + 1],
+    };
+    let mut boundary = vec![false; h.num_vertices()];
+    for (e, weight) in h.weights().iter().enumerate() {
+        let connectivity = h.connectivity(e as Index, blocks);
+        metrics.connectivity[connectivity] += 1;
+        if connectivity > 1 {
+            metrics.cut += weight;
+            metrics.km1 += weight * (connectivity - 1) as f32;
+            metrics.soed += weight * connectivity as f32;
+            for v in h.net(e as Index) {
+                boundary[*v as usize] = true;
+            }
+        }
+    }
+    metrics.boundary_vertices = boundary.iter().filter(|b| **b).count();
+    let total: f32 = metrics.block_capacities.iter().sum();
+    let heaviest = metrics.block_capacities.iter().copied().fold(0.0, f32::max);
+    metrics.imbalance = heaviest / (total / k as f32) - 1.0;
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_small() {
+        let mut h = Hypergraph::new(vec![1.0, 1.0, 2.0, 1.0, 1.0]);
+        h.add_net(2.0, &[0, 1]);
+        h.add_net(1.0, &[1, 2, 3]);
+        h.add_net(3.0, &[0, 2, 4]);
+
+        let metrics = validate(&h, &[0, 0, 1, 2, 1], 3, false).unwrap();
+        assert_eq!(metrics.cut, 4.0);
+        assert_eq!(metrics.km1, 5.0);
+        assert_eq!(metrics.soed, 9.0);
+        assert_eq!(metrics.block_capacities, [2.0, 3.0, 1.0]);
+        assert_eq!(metrics.imbalance, 0.5);
+        assert_eq!(metrics.boundary_vertices, 5);
+        assert_eq!(metrics.connectivity, [0, 1, 1, 1]);
+
+        assert_eq!(
+            validate(&h, &[0, 0, 1], 2, false),
+            Err(InvalidPartition::Length {
+                expected: 5,
+                found: 3
+            })
+        );
+        assert_eq!(
+            validate(&h, &[0, 0, 3, 1, 1], 3, false),
+            Err(InvalidPartition::BlockOutOfRange {
+                vertex: 2,
+                block: 3,
+                k: 3
+            })
+        );
+        assert_eq!(
+            validate(&h, &[0, 0, 2, 2, 0], 3, false),
+            Err(InvalidPartition::EmptyBlock(1))
+        );
+        assert!(validate(&h, &[0, 0, 2, 2, 0], 3, true).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::Debug;
+use std::ops::AddAssign;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Number of contractions or uncontractions between progress events of
+/// n-level partitioning.
+pub(crate) const BATCH_SIZE: usize = 1024;
+
+/// Receives progress events while partitioning, e.g. to drive a progress bar.
+/// Events may come from several threads at once when tries run in parallel.
+pub trait Observer: Debug + Send + Sync {
+    fn notify(&self, event: &Event);
+}
+
+/// A step of a partitioning run. Every try, and every V-cycle of the memetic
+/// algorithm, reports its own coarsening and uncoarsening.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The hypergraph being coarsened is down to `num_vertices`. Coarsening
+    /// stops at about `target` vertices.
+    Coarsening { num_vertices: usize, target: usize },
+    /// The best initial partition of the coarsest hypergraph.
+    InitialPartition {
+        num_vertices: usize,
+        imbalance: f32,
+        cut: f32,
+    },
+    /// A batch of uncontractions, or a level of a multilevel hierarchy, has
+    /// been undone and refined.
+    Uncoarsening {
+        num_vertices: usize,
+        imbalance: f32,
+        cut: f32,
+    },
+    /// The result that is about to be returned.
+    Finished {
+        imbalance: f32,
+        cut: f32,
+        truncated: bool,
+    },
+}
+
+/// Time spent in each phase. Phases are summed over all tries, so with
+/// parallel repetitions they can add up to more than the wall-clock time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timings {
+    pub preprocessing: Duration,
+    pub coarsening: Duration,
+    pub initial: Duration,
+    /// Uncoarsening, including the refinement on every level.
+    pub refinement: Duration,
+}
+
+impl AddAssign for Timings {
+    fn add_assign(&mut self, other: Timings) {
+        self.preprocessing += other.preprocessing;
+        self.coarsening += other.coarsening;
+        self.initial += other.initial;
+        self.refinement += other.refinement;
+    }
+}
+
+/// The observer and phase timings of a run.
+#[derive(Debug)]
+pub(crate) struct Progress<'a> {
+    observer: Option<&'a dyn Observer>,
+    timings: Mutex<Timings>,
+}
+
+impl<'a> Progress<'a> {
+    pub(crate) fn new(config: &'a Config) -> Self {
+        Progress {
+            observer: config.observer.as_deref(),
+            timings: Mutex::default(),
+        }
+    }
+
+    /// Sends the event built by `event`, which is only called if there is an
+    /// observer.
+    pub(crate) fn notify(&self, event: impl FnOnce() -> Event) {
+        if let Some(observer) = self.observer {
+            observer.notify(&event());
+        }
+    }
+
+    /// Runs `f`, adding its duration to the phase selected by `phase`.
+    pub(crate) fn time<T>(
+        &self,
+        phase: impl FnOnce(&mut Timings) -> &mut Duration,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = f();
+        *phase(&mut self.timings.lock().unwrap()) += start.elapsed();
+        result
+    }
+
+    pub(crate) fn timings(&self) -> Timings {
+        *self.timings.lock().unwrap()
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::iter::zip;
+
+use rayon::prelude::*;
+
+use crate::bipartite::*;
+
+/// Drops nets with fewer than two pins, since they can never be cut, and merges
+/// nets with identical pins into one net carrying their summed weight. Pin
+/// lists must be sorted and free of duplicates. Neither change affects the cut
+/// of any partition, so results carry over to the original nets unchanged.
+pub(crate) fn remove_redundant_nets(
+    mut weights: Vec<f32>,
+    nets: Vec<Vec<Index>>,
+) -> (Vec<f32>, Vec<Vec<Index>>) {
+    let fingerprints: Vec<u64> = nets
+        .par_iter()
+        .map(|net| {
+            let mut hasher = DefaultHasher::new();
+            net.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    let mut order: Vec<_> = (0..nets.len()).filter(|e| nets[*e].len() > 1).collect();
+    order.par_sort_unstable_by_key(|e| (fingerprints[*e], *e));
+
+    // Within a run of equal fingerprints, each net is merged into the first
+    // earlier net with the same pins. Distinct nets only share a run on a hash
+    // collision, so runs are short unless there are many identical nets, in
+    // which case the first net of the run is the match.
+    let mut keep = vec![false; nets.len()];
+    let mut run_start = 0;
+    for idx in 0..order.len() {
+        let e = order[idx];
+        if fingerprints[e] != fingerprints[order[run_start]] {
+            run_start = idx;
+        }
+        let representative = order[run_start..idx]
+            .iter()
+            .copied()
+            .find(|r| keep[*r] && nets[*r] == nets[e]);
+        match representative {
+            Some(r) => weights[r] += weights[e],
+            None => keep[e] = true,
+        }
+    }
+
+    zip(zip(weights, nets), keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(net, _)| net)
+        .unzip()
+}
+
+/// Drops nets with more than `max_size` pins. Such nets, like clock and reset
+/// nets, are expensive to handle but give little guidance to the partitioner.
+/// Objectives must be evaluated against the original nets to count them.
+pub(crate) fn remove_large_nets(
+    weights: Vec<f32>,
+    nets: Vec<Vec<Index>>,
+    max_size: usize,
+) -> (Vec<f32>, Vec<Vec<Index>>) {
+    zip(weights, nets)
+        .filter(|(_, net)| net.len() <= max_size)
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_identical_nets() {
+        let weights = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let nets = vec![vec![0, 1], vec![2], vec![0, 1, 2], vec![0, 1], vec![]];
+        let (weights, nets) = remove_redundant_nets(weights, nets);
+        assert_eq!(weights, vec![5.0, 3.0]);
+        assert_eq!(nets, vec![vec![0, 1], vec![0, 1, 2]]);
+    }
+}
@@ -1,111 +1,266 @@
 use std::collections::BinaryHeap;
 
-use bitvec::prelude::*;
 use ordered_float::OrderedFloat;
+use rayon::prelude::*;
 
 use crate::bipartite::*;
+use crate::budget::Budget;
+use crate::coarsen::Level;
+use crate::config::Refinement;
+use crate::observer::{Event, Progress, BATCH_SIZE};
 
 const C: usize = 20;
+const LP_ROUNDS: usize = 5;
 
+type GainQueue = BinaryHeap<(OrderedFloat<f32>, Index)>;
+
+/// Undoes the contractions in reverse order, running FM around every
+/// uncontracted pair on the border. Once the budget is exhausted, the remaining
+/// contractions are only undone, so the partition still covers all of `h`.
 pub(crate) fn uncoarsen(
     h: &mut Bipartite,
     size_constraint: f32,
     mementos: Vec<Memento>,
+    budget: &Budget,
+    progress: &Progress,
     partition: &mut Bipartition,
 ) {
     let mut gain_pq = BinaryHeap::new();
     let mut gain_vec = vec![0.0; h.pin_index_space_size()];
 
-    for memento in mementos.into_iter().rev() {
+    let num_mementos = mementos.len();
+    for (idx, memento) in mementos.into_iter().rev().enumerate() {
         let u = memento.u;
         let v = memento.v;
         h.uncontract(memento);
         partition[v as usize] = partition[u as usize];
-        let mut b1_capacity = h
-            .pins()
-            .filter(|v| partition[*v as usize])
-            .map(|v| h.capacity(v))
-            .sum::<f32>();
-        let mut b2_capacity = h
-            .pins()
-            .filter(|v| !partition[*v as usize])
-            .map(|v| h.capacity(v))
-            .sum::<f32>();
-
-        let border_u = h
-            .incident_pins(u)
-            .any(|p| partition[p as usize] != partition[u as usize]);
-        if border_u {
-            let gain = gain(h, u, partition);
-            gain_pq.push((OrderedFloat(gain), u));
-            gain_vec[u as usize] = gain;
-        }
-        let border_v = h
-            .incident_pins(v)
-            .any(|p| partition[p as usize] != partition[v as usize]);
-        if border_v {
-            let gain = gain(h, v, partition);
-            gain_pq.push((OrderedFloat(gain), v));
-            gain_vec[v as usize] = gain;
+
+        if !budget.exhausted() {
+            let border_u = push_if_border(h, u, partition, &mut gain_pq, &mut gain_vec);
+            let border_v = push_if_border(h, v, partition, &mut gain_pq, &mut gain_vec);
+            if border_u || border_v {
+                fm(
+                    h,
+                    size_constraint,
+                    partition,
+                    &mut gain_pq,
+                    &mut gain_vec,
+                    budget,
+                );
+            }
         }
-        if !border_u && !border_v {
-            continue;
+
+        if (idx + 1).is_multiple_of(BATCH_SIZE) || idx + 1 == num_mementos {
+            progress.notify(|| uncoarsening_event(h, partition));
         }
+    }
+}
 
-        let mut steps = vec![];
-        let mut best_step = 0;
-        let mut best_gain = 0.0;
-        let mut current_gain = 0.0;
-        let mut non_increase_count = 0;
-        while let Some((OrderedFloat(g), v)) = gain_pq.pop() {
-            if g != gain_vec[v as usize] {
-                gain_pq.push((OrderedFloat(gain_vec[v as usize]), v));
-                continue;
-            }
-            if g <= 0.0 {
-                non_increase_count += 1;
-            } else {
-                non_increase_count = 0;
-            }
-            if non_increase_count >= C {
-                break;
+/// Projects the partition of the coarsest level back onto `h`, refining the
+/// partition of every level on the way. Refinement stops once the budget is
+/// exhausted, but the projection always reaches `h`.
+pub(crate) fn uncoarsen_multilevel(
+    h: &Bipartite,
+    size_constraint: f32,
+    levels: &[Level],
+    refinement: Refinement,
+    budget: &Budget,
+    progress: &Progress,
+    mut partition: Bipartition,
+) -> Bipartition {
+    for (idx, level) in levels.iter().enumerate().rev() {
+        let fine = if idx == 0 { h } else { &levels[idx - 1].h };
+        partition = (0..fine.pin_index_space_size())
+            .into_par_iter()
+            .map(|v| fine.enabled(v as Index) && partition[level.map[v] as usize])
+            .collect();
+        match refinement {
+            Refinement::Fm => refine(fine, size_constraint, &mut partition, budget),
+            Refinement::LabelPropagation => {
+                label_propagation(fine, size_constraint, &mut partition, budget)
             }
+        }
+        progress.notify(|| uncoarsening_event(fine, &partition));
+    }
+    partition
+}
 
-            current_gain += g;
-            partition[v as usize] = !partition[v as usize];
-            steps.push(v);
-            if partition[v as usize] {
-                b1_capacity += h.capacity(v);
-                b2_capacity += h.capacity(v);
-            } else {
-                b1_capacity -= h.capacity(v);
-                b2_capacity += h.capacity(v);
-            }
-            if current_gain >= best_gain
-                && b1_capacity <= size_constraint
-                && b2_capacity <= size_constraint
-            {
-                best_step = steps.len();
-                best_gain = current_gain;
-            }
-            for p in h.incident_pins(v) {
-                gain_vec[p as usize] = gain(h, p, partition);
+fn uncoarsening_event(h: &Bipartite, partition: &Bipartition) -> Event {
+    let (imbalance, cut) = h.evaluate_bipartition(partition);
+    Event::Uncoarsening {
+        num_vertices: h.num_pins(),
+        imbalance,
+        cut,
+    }
+}
+
+/// Parallel label propagation. Gains are computed in parallel against the
+/// current partition, and the positive moves are applied one direction at a
+/// time. Moving vertices in the same direction can't make the gains of the
+/// other moves worse, so a round never increases the cut.
+pub(crate) fn label_propagation(
+    h: &Bipartite,
+    size_constraint: f32,
+    partition: &mut Bipartition,
+    budget: &Budget,
+) {
+    let pins: Vec<_> = h.pins().collect();
+    let mut capacities = [0.0; 2];
+    for v in pins.iter() {
+        capacities[partition[*v as usize] as usize] += h.capacity(*v);
+    }
+
+    for _ in 0..LP_ROUNDS {
+        if budget.exhausted() {
+            break;
+        }
+        let mut moved = false;
+        for from in [false, true] {
+            let mut moves: Vec<_> = pins
+                .par_iter()
+                .copied()
+                .filter(|v| partition[*v as usize] == from)
+                .filter_map(|v| {
+                    let gain = gain(h, v, partition);
+                    (gain > 0.0).then_some((OrderedFloat(gain), v))
+                })
+                .collect();
+            moves.par_sort_unstable_by(|a, b| b.cmp(a));
+
+            for (_, v) in moves {
+                let c = h.capacity(v);
+                if capacities[!from as usize] + c <= size_constraint {
+                    partition[v as usize] = !from;
+                    capacities[from as usize] -= c;
+                    capacities[!from as usize] += c;
+                    moved = true;
+                }
             }
         }
-        for idx in (best_step..steps.len()).rev() {
-            let step = steps[idx];
-            partition[step as usize] = !partition[step as usize];
+        if !moved {
+            break;
         }
     }
 }
 
+/// Runs a single FM pass seeded with every border vertex of the partition.
+pub(crate) fn refine(
+    h: &Bipartite,
+    size_constraint: f32,
+    partition: &mut Bipartition,
+    budget: &Budget,
+) {
+    let mut gain_pq = BinaryHeap::new();
+    let mut gain_vec = vec![0.0; h.pin_index_space_size()];
+    let mut any_border = false;
+    for v in h.pins() {
+        any_border |= push_if_border(h, v, partition, &mut gain_pq, &mut gain_vec);
+    }
+    if any_border {
+        fm(
+            h,
+            size_constraint,
+            partition,
+            &mut gain_pq,
+            &mut gain_vec,
+            budget,
+        );
+    }
+}
+
+fn push_if_border(
+    h: &Bipartite,
+    v: Index,
+    partition: &Bipartition,
+    gain_pq: &mut GainQueue,
+    gain_vec: &mut [f32],
+) -> bool {
+    let border = h
+        .incident_pins(v)
+        .any(|p| partition[p as usize] != partition[v as usize]);
+    if border {
+        let gain = gain(h, v, partition);
+        gain_pq.push((OrderedFloat(gain), v));
+        gain_vec[v as usize] = gain;
+    }
+    border
+}
+
+fn fm(
+    h: &Bipartite,
+    size_constraint: f32,
+    partition: &mut Bipartition,
+    gain_pq: &mut GainQueue,
+    gain_vec: &mut [f32],
+    budget: &Budget,
+) {
+    let mut b1_capacity = h
+        .pins()
+        .filter(|v| partition[*v as usize])
+        .map(|v| h.capacity(v))
+        .sum::<f32>();
+    let mut b2_capacity = h
+        .pins()
+        .filter(|v| !partition[*v as usize])
+        .map(|v| h.capacity(v))
+        .sum::<f32>();
+
+    let mut steps = vec![];
+    let mut best_step = 0;
+    let mut best_gain = 0.0;
+    let mut current_gain = 0.0;
+    let mut non_increase_count = 0;
+    while let Some((OrderedFloat(g), v)) = gain_pq.pop() {
+        if g != gain_vec[v as usize] {
+            gain_pq.push((OrderedFloat(gain_vec[v as usize]), v));
+            continue;
+        }
+        if g <= 0.0 {
+            non_increase_count += 1;
+        } else {
+            non_increase_count = 0;
+        }
+        if non_increase_count >= C || budget.exhausted() {
+            break;
+        }
+
+        current_gain += g;
+        partition[v as usize] = !partition[v as usize];
+        steps.push(v);
+        if partition[v as usize] {
+            b1_capacity += h.capacity(v);
+            b2_capacity -= h.capacity(v);
+        } else {
+            b1_capacity -= h.capacity(v);
+            b2_capacity += h.capacity(v);
+        }
+        if current_gain >= best_gain
+            && b1_capacity <= size_constraint
+            && b2_capacity <= size_constraint
+        {
+            best_step = steps.len();
+            best_gain = current_gain;
+        }
+        for p in h.incident_pins(v) {
+            gain_vec[p as usize] = gain(h, p, partition);
+        }
+    }
+    for idx in (best_step..steps.len()).rev() {
+        let step = steps[idx];
+        partition[step as usize] = !partition[step as usize];
+    }
+}
+
 fn gain(h: &Bipartite, v: Index, partition: &Bipartition) -> f32 {
+    if h.is_graph() {
+        return graph_gain(h, v, partition);
+    }
     let b_v = partition[v as usize];
     let upside: f32 = h
         .incident_nets(v)
         .filter(|e| {
             h.pins_in_net(*e)
-                .filter(|n| partition[*n as usize] == !b_v)
+                .filter(|n| partition[*n as usize] != b_v)
                 .count()
                 == h.pins_in_net(*e).len() - 1
         })
@@ -123,3 +278,35 @@ fn gain(h: &Bipartite, v: Index, partition: &Bipartition) -> f32 {
         .sum();
     upside - downside
 }
+
+/// Fast path of `gain` for graphs. Moving `v` uncuts every edge to the other
+/// block and cuts every edge within its block, so only the other pin of each
+/// edge matters.
+fn graph_gain(h: &Bipartite, v: Index, partition: &Bipartition) -> f32 {
+    let b_v = partition[v as usize];
+    h.incident_nets(v)
+        .filter_map(|e| {
+            let u = h.pins_in_net(e).find(|u| *u != v)?;
+            let w = h.weight(e);
+            Some(if partition[u as usize] != b_v { w } else { -w })
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn fm_tracks_both_block_capacities() {
+        // Moving vertex 1 over uncuts the only net and makes the partition
+        // feasible, but only if its capacity leaves the second block.
+        let nets: [&[Index]; 1] = [&[0, 1]];
+        let h = Bipartite::new(&[1.0; 4], &[1.0], &nets);
+        let mut partition = vec![true, false, false, false];
+        let budget = Budget::new(&Config::default());
+        refine(&h, 2.0, &mut partition, &budget);
+        assert_eq!(partition, [true, true, false, false]);
+    }
+}